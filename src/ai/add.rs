@@ -0,0 +1,132 @@
+use chrono::{Local, NaiveDate};
+use colored::*;
+use serde::Deserialize;
+use std::error::Error;
+
+use super::context::TodoContext;
+use super::privacy::Privacy;
+use super::provider::{CompletionRequest, Message};
+use crate::arguments::{add_todo::build_todo, confirm::confirm, models::Todo};
+use crate::database::DBtodo;
+
+// What the model is asked to fill in, mirrors the `--add` flags
+#[derive(Deserialize, Debug)]
+struct TodoDraft {
+    text: String,
+    #[serde(default)]
+    desc: Option<String>,
+    #[serde(default)]
+    owner: Option<String>,
+    #[serde(default)]
+    due: Option<String>,
+    #[serde(default)]
+    priority: Option<String>,
+    #[serde(default)]
+    topic: Option<String>,
+}
+
+// CREATE A TODO FROM A NATURAL LANGUAGE SENTENCE
+pub async fn add_from_prompt(
+    sentence: String,
+    yes: bool,
+    show_request: bool,
+) -> Result<(), Box<dyn Error>> {
+    let db = DBtodo::new()?;
+    // The topics and owners of the todos closest to the sentence, within the context budget
+    let todos = TodoContext::load()?.relevant(&Privacy::load()?.todos(&db.get_todos()?), &sentence);
+
+    let response = super::client(show_request)?
+        .complete(&CompletionRequest {
+            messages: vec![
+                Message::system(system_prompt(&todos)),
+                Message::user(sentence),
+            ],
+            json: true,
+            ..Default::default()
+        })
+        .await?;
+
+    let draft: TodoDraft = super::parse_json(&response.text)?;
+    let todo = validate(draft)?;
+
+    print_todo(&todo);
+    if !yes && !confirm("Add this todo?") {
+        println!("Nothing added.");
+        return Ok(());
+    }
+
+    db.add_todo(&todo)?;
+    println!("✅ Todo added successfully!");
+    Ok(())
+}
+
+fn system_prompt(todos: &[Todo]) -> String {
+    let today = Local::now();
+
+    // Nudge the model towards the names already in use
+    let mut topics = todos.iter().map(|t| t.topic.as_str()).collect::<Vec<_>>();
+    topics.sort();
+    topics.dedup();
+    let mut owners = todos.iter().map(|t| t.owner.as_str()).collect::<Vec<_>>();
+    owners.sort();
+    owners.dedup();
+
+    format!(
+        r#"ROLE: You turn a sentence into a todo item for a todo application.
+Answer with a single JSON object and nothing else:
+{{
+  "text": "short imperative summary of the task",
+  "desc": "extra details from the sentence, or null",
+  "owner": "person responsible, or null if nobody is named",
+  "due": "YYYY-MM-DD, or null if no date is implied",
+  "priority": "low | normal | medium | high, or null",
+  "topic": "one or two word category, or null"
+}}
+RULES:
+- Today is {} ({}). Resolve relative dates like "next friday" from today.
+- Reuse an existing topic or owner when it clearly matches.
+- Existing topics: {}
+- Existing owners: {}"#,
+        today.format("%A"),
+        today.format("%Y-%m-%d"),
+        topics.join(", "),
+        owners.join(", "),
+    )
+}
+
+// Apply the same rules as `--add`, plus a real calendar date for the due field
+fn validate(draft: TodoDraft) -> Result<Todo, Box<dyn Error>> {
+    let clean = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty() && v != "null")
+    };
+
+    let due = clean(draft.due);
+    if let Some(due) = &due {
+        NaiveDate::parse_from_str(due, "%Y-%m-%d")
+            .map_err(|_| format!("The model returned an invalid due date: '{}'", due))?;
+    }
+
+    build_todo(
+        draft.text,
+        clean(draft.topic),
+        clean(draft.priority),
+        clean(draft.owner),
+        due,
+        clean(draft.desc),
+    )
+}
+
+fn print_todo(todo: &Todo) {
+    println!();
+    println!("{}", "🤖 Here's the todo I understood:".bold());
+    println!();
+    println!("  {:<10} {}", "Todo:".bright_black(), todo.text.bold());
+    println!("  {:<10} {}", "Details:".bright_black(), todo.desc);
+    println!("  {:<10} {}", "Owner:".bright_black(), todo.owner);
+    println!("  {:<10} {}", "Due:".bright_black(), todo.due);
+    println!("  {:<10} {}", "Priority:".bright_black(), todo.priority);
+    println!("  {:<10} {}", "Topic:".bright_black(), todo.topic);
+    println!();
+}
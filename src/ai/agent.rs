@@ -0,0 +1,377 @@
+use colored::*;
+use serde_json::{Value, json};
+use std::error::Error;
+
+use super::privacy::Privacy;
+use super::provider::{CompletionRequest, Message, Tool, ToolCall};
+use crate::arguments::{
+    add_todo::{build_todo, normalize_priority},
+    confirm::confirm,
+    models::Todo,
+};
+use crate::configs::AppConfigs;
+use crate::database::DBtodo;
+
+// Stop runaway loops where the model keeps calling tools
+const MAX_STEPS: usize = 12;
+
+// LET THE MODEL READ AND CHANGE THE TODO LIST THROUGH TOOL CALLS
+pub async fn run_agent(
+    prompt: String,
+    yes: bool,
+    dry_run: bool,
+    show_request: bool,
+) -> Result<(), Box<dyn Error>> {
+    let client = super::client(show_request)?;
+    let configs = AppConfigs::new()?;
+    let mut agent = Agent {
+        db: DBtodo::new()?,
+        privacy: Privacy::from_config(&configs)?,
+        // The same statuses as the board columns
+        statuses: configs.tui.statuses,
+        yes,
+        dry_run,
+        planned: 0,
+    };
+
+    let mut messages = vec![
+        Message::system(system_prompt(&agent.statuses)),
+        Message::user(prompt),
+    ];
+
+    for _ in 0..MAX_STEPS {
+        let response = client
+            .complete(&CompletionRequest {
+                messages: messages.clone(),
+                tools: tools(&agent.statuses),
+                ..Default::default()
+            })
+            .await?;
+
+        if response.tool_calls.is_empty() {
+            println!();
+            println!("🤖 {}", response.text.trim());
+            println!();
+            if dry_run {
+                println!(
+                    "{}",
+                    format!(
+                        "Dry run: {} change(s) planned, nothing was modified.",
+                        agent.planned
+                    )
+                    .yellow()
+                );
+            }
+            return Ok(());
+        }
+
+        messages.push(Message::assistant(
+            response.text.clone(),
+            response.tool_calls.clone(),
+        ));
+        for call in &response.tool_calls {
+            let result = agent.execute(call);
+            messages.push(Message::tool_result(call, result));
+        }
+    }
+
+    Err(format!("Stopped after {} steps without a final answer", MAX_STEPS).into())
+}
+
+fn system_prompt(statuses: &[String]) -> String {
+    format!(
+        "ROLE: You are an assistant that manages the user's todo list with the tools provided.
+RULES:
+- Call list_todos before changing anything so you use real ids
+- Only change what the user asked for
+- Valid statuses: {}
+- Valid priorities: Low, Normal, Medium, High
+- Due dates use YYYY-MM-DD. Today is {}
+- When you're done, reply with a short summary of what changed",
+        statuses.join(", "),
+        chrono::Local::now().format("%A %Y-%m-%d"),
+    )
+}
+
+fn tools(statuses: &[String]) -> Vec<Tool> {
+    let id = json!({ "type": "integer", "description": "The todo id" });
+    let fields = json!({
+        "text": { "type": "string", "description": "Short summary of the task" },
+        "desc": { "type": "string", "description": "Longer description" },
+        "topic": { "type": "string" },
+        "priority": { "type": "string", "enum": ["Low", "Normal", "Medium", "High"] },
+        "owner": { "type": "string" },
+        "due": { "type": "string", "description": "YYYY-MM-DD" }
+    });
+
+    let mut edit_fields = fields.clone();
+    edit_fields["id"] = id.clone();
+
+    vec![
+        Tool {
+            name: "list_todos",
+            description: "List todos, optionally only the ones with a given status",
+            parameters: json!({
+                "type": "object",
+                "properties": { "status": { "type": "string", "enum": statuses } }
+            }),
+        },
+        Tool {
+            name: "add_todo",
+            description: "Create a new todo",
+            parameters: json!({ "type": "object", "properties": fields, "required": ["text"] }),
+        },
+        Tool {
+            name: "update_status",
+            description: "Change the status of a todo",
+            parameters: json!({
+                "type": "object",
+                "properties": { "id": id, "status": { "type": "string", "enum": statuses } },
+                "required": ["id", "status"]
+            }),
+        },
+        Tool {
+            name: "edit_todo",
+            description: "Change the text, description, topic, priority, owner or due date of a todo. Only pass the fields to change",
+            parameters: json!({ "type": "object", "properties": edit_fields, "required": ["id"] }),
+        },
+        Tool {
+            name: "delete_todo",
+            description: "Delete a todo",
+            parameters: json!({
+                "type": "object",
+                "properties": { "id": id },
+                "required": ["id"]
+            }),
+        },
+    ]
+}
+
+struct Agent {
+    db: DBtodo,
+    privacy: Privacy,
+    statuses: Vec<String>,
+    yes: bool,
+    dry_run: bool,
+    planned: usize,
+}
+
+type FieldAccess = fn(&mut Todo) -> &mut String;
+
+// What happens to a change the model asked for
+enum Decision {
+    Apply,
+    Skip(&'static str),
+}
+
+impl Agent {
+    // Run a tool call and describe the outcome for the model
+    fn execute(&mut self, call: &ToolCall) -> String {
+        let result = match call.name.as_str() {
+            "list_todos" => self.list_todos(&call.arguments),
+            "add_todo" => self.add_todo(&call.arguments),
+            "update_status" => self.update_status(&call.arguments),
+            "edit_todo" => self.edit_todo(&call.arguments),
+            "delete_todo" => self.delete_todo(&call.arguments),
+            other => Err(format!("Unknown tool '{}'", other).into()),
+        };
+
+        match result {
+            Ok(output) => output,
+            Err(e) => json!({ "error": e.to_string() }).to_string(),
+        }
+    }
+
+    // Show the planned change and ask before touching the database
+    fn review(&mut self, plan: String) -> Decision {
+        println!("{}", plan);
+        self.planned += 1;
+
+        if self.dry_run {
+            println!("   {}", "(dry run, not applied)".bright_black());
+            return Decision::Skip("Dry run: the change was recorded but not applied.");
+        }
+        if !self.yes && !confirm("   Apply this change?") {
+            return Decision::Skip("The user declined this change.");
+        }
+        Decision::Apply
+    }
+
+    fn list_todos(&self, args: &Value) -> Result<String, Box<dyn Error>> {
+        let status = arg_str(args, "status");
+        let todos = self
+            .db
+            .get_todos()?
+            .into_iter()
+            .filter(|t| {
+                status
+                    .as_ref()
+                    .is_none_or(|s| t.status.eq_ignore_ascii_case(s))
+            })
+            .collect::<Vec<Todo>>();
+
+        // The model only sees what the privacy settings allow
+        let todos = self
+            .privacy
+            .todos(&todos)
+            .iter()
+            .map(todo_json)
+            .collect::<Vec<Value>>();
+
+        Ok(Value::Array(todos).to_string())
+    }
+
+    fn add_todo(&mut self, args: &Value) -> Result<String, Box<dyn Error>> {
+        let todo = build_todo(
+            arg_str(args, "text").ok_or("Missing 'text'")?,
+            arg_str(args, "topic"),
+            arg_str(args, "priority"),
+            arg_str(args, "owner"),
+            arg_str(args, "due"),
+            arg_str(args, "desc"),
+        )?;
+
+        let plan = format!(
+            "➕ Add \"{}\" ({}, {}, owner {}, due {})",
+            todo.text.bold(),
+            todo.priority,
+            todo.topic,
+            todo.owner,
+            todo.due
+        );
+        match self.review(plan) {
+            Decision::Apply => {
+                let id = self.db.add_todo(&todo)?;
+                Ok(json!({ "ok": true, "id": id }).to_string())
+            }
+            Decision::Skip(reason) => Ok(json!({ "ok": false, "reason": reason }).to_string()),
+        }
+    }
+
+    fn update_status(&mut self, args: &Value) -> Result<String, Box<dyn Error>> {
+        let todo = self.todo_from(args)?;
+        let status = self.normalize_status(&arg_str(args, "status").ok_or("Missing 'status'")?)?;
+
+        let plan = format!(
+            "🔄 #{} \"{}\": {} → {}",
+            todo.id,
+            todo.text.bold(),
+            todo.status,
+            status
+        );
+        match self.review(plan) {
+            Decision::Apply => {
+                let found = self.db.update_todo(todo.id as i32, Some(status))?;
+                Ok(json!({ "ok": found }).to_string())
+            }
+            Decision::Skip(reason) => Ok(json!({ "ok": false, "reason": reason }).to_string()),
+        }
+    }
+
+    fn edit_todo(&mut self, args: &Value) -> Result<String, Box<dyn Error>> {
+        let before = self.todo_from(args)?;
+        let mut after = before.clone();
+        let mut changes = Vec::new();
+
+        let fields: [(&str, FieldAccess); 6] = [
+            ("text", |t| &mut t.text),
+            ("desc", |t| &mut t.desc),
+            ("topic", |t| &mut t.topic),
+            ("priority", |t| &mut t.priority),
+            ("owner", |t| &mut t.owner),
+            ("due", |t| &mut t.due),
+        ];
+        for (name, field) in fields {
+            let Some(mut value) = arg_str(args, name) else {
+                continue;
+            };
+            if name == "priority" {
+                value = normalize_priority(&value)?;
+            }
+            let current = field(&mut after);
+            if *current != value {
+                changes.push(format!("{}: {} → {}", name, current, value));
+                *current = value;
+            }
+        }
+
+        if changes.is_empty() {
+            return Ok(json!({ "ok": true, "note": "Nothing to change" }).to_string());
+        }
+
+        let plan = format!(
+            "✏️  Edit #{} \"{}\": {}",
+            before.id,
+            before.text.bold(),
+            changes.join(", ")
+        );
+        match self.review(plan) {
+            Decision::Apply => {
+                self.db.edit_todo(&after)?;
+                Ok(json!({ "ok": true }).to_string())
+            }
+            Decision::Skip(reason) => Ok(json!({ "ok": false, "reason": reason }).to_string()),
+        }
+    }
+
+    fn delete_todo(&mut self, args: &Value) -> Result<String, Box<dyn Error>> {
+        let todo = self.todo_from(args)?;
+
+        let plan = format!("🗑️  Delete #{} \"{}\"", todo.id, todo.text.bold());
+        match self.review(plan) {
+            Decision::Apply => {
+                let found = self.db.delete_todo(todo.id as i32)?;
+                Ok(json!({ "ok": found }).to_string())
+            }
+            Decision::Skip(reason) => Ok(json!({ "ok": false, "reason": reason }).to_string()),
+        }
+    }
+
+    fn todo_from(&self, args: &Value) -> Result<Todo, Box<dyn Error>> {
+        let id = args
+            .get("id")
+            .and_then(|id| id.as_i64().or_else(|| id.as_str()?.parse().ok()))
+            .ok_or("Missing or invalid 'id'")?;
+
+        let todo = self
+            .db
+            .get_todo(id as i32)?
+            .ok_or(format!("No todo found with id: {}", id))?;
+        // Todos the model can't see can't be changed either
+        if self.privacy.todo(&todo).is_none() {
+            return Err(
+                format!("Todo {} is excluded from AI in the [PRIVACY] settings", id).into(),
+            );
+        }
+        Ok(todo)
+    }
+
+    fn normalize_status(&self, status: &str) -> Result<String, Box<dyn Error>> {
+        self.statuses
+            .iter()
+            .find(|s| s.eq_ignore_ascii_case(status.trim()))
+            .cloned()
+            .ok_or_else(|| format!("Status must be one of {}", self.statuses.join(", ")).into())
+    }
+}
+
+fn arg_str(args: &Value, key: &str) -> Option<String> {
+    args.get(key)
+        .and_then(|v| v.as_str())
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+fn todo_json(todo: &Todo) -> Value {
+    json!({
+        "id": todo.id,
+        "text": todo.text,
+        "desc": todo.desc,
+        "status": todo.status,
+        "priority": todo.priority,
+        "topic": todo.topic,
+        "owner": todo.owner,
+        "due": todo.due,
+        "parent_id": todo.parent_id,
+    })
+}
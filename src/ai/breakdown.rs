@@ -0,0 +1,205 @@
+use colored::*;
+use serde::Deserialize;
+use std::error::Error;
+use std::io::{self, Write};
+
+use super::privacy::Privacy;
+use super::provider::{CompletionRequest, Message};
+use crate::arguments::{add_todo::build_todo, models::Todo};
+use crate::database::DBtodo;
+
+#[derive(Deserialize, Debug)]
+struct Step {
+    text: String,
+    #[serde(default)]
+    estimate: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Breakdown {
+    steps: Vec<Step>,
+}
+
+// SPLIT A TODO INTO CONCRETE STEPS AND CREATE THE ONES THE USER PICKS AS SUBTASKS
+pub async fn breakdown(id: i32, show_request: bool) -> Result<(), Box<dyn Error>> {
+    let db = DBtodo::new()?;
+    let todo = db
+        .get_todo(id)?
+        .ok_or(format!("No todo found with id: {}", id))?;
+    let shared = Privacy::load()?
+        .todo(&todo)
+        .ok_or("This todo's topic is excluded from AI in the [PRIVACY] settings")?;
+
+    let response = super::client(show_request)?
+        .complete(&CompletionRequest {
+            messages: vec![
+                Message::system(SYSTEM_PROMPT),
+                Message::user(format!(
+                    "TODO: {}\nDESCRIPTION: {}\nPRIORITY: {}\nDUE: {}",
+                    shared.text, shared.desc, shared.priority, shared.due
+                )),
+            ],
+            json: true,
+            ..Default::default()
+        })
+        .await?;
+
+    let breakdown: Breakdown = super::parse_json(&response.text)?;
+    let steps = breakdown
+        .steps
+        .into_iter()
+        .filter(|step| !step.text.trim().is_empty())
+        .collect::<Vec<Step>>();
+
+    if steps.is_empty() {
+        println!("🤖 The model didn't suggest any steps.");
+        return Ok(());
+    }
+
+    println!();
+    println!("{} {}", "🤖 Steps for".bold(), todo.text.bold());
+    println!();
+    for (i, step) in steps.iter().enumerate() {
+        match &step.estimate {
+            Some(estimate) => println!(
+                "  {:>2}. {} {}",
+                i + 1,
+                step.text,
+                format!("(~{})", estimate).bright_black()
+            ),
+            None => println!("  {:>2}. {}", i + 1, step.text),
+        }
+    }
+    println!();
+
+    print!("Create which steps as subtasks? (e.g. 1,3-5, all, or Enter for none): ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+
+    let picked = parse_selection(&answer, steps.len())?;
+    let subtasks = picked
+        .iter()
+        .map(|index| subtask_for(&todo, &steps[*index], *index + 1, steps.len()))
+        .collect::<Result<Vec<Todo>, Box<dyn Error>>>()?;
+    db.add_todos(&subtasks)?;
+
+    if picked.is_empty() {
+        println!("Nothing added.");
+    } else {
+        println!("✅ Added {} subtask(s) to todo #{}", picked.len(), todo.id);
+    }
+    Ok(())
+}
+
+const SYSTEM_PROMPT: &str = r#"ROLE: You break a todo item into smaller steps for a todo application.
+Answer with a single JSON object and nothing else:
+{
+  "steps": [
+    { "text": "concrete, actionable step", "estimate": "rough effort like 30m, 2h or 1d, or null" }
+  ]
+}
+RULES:
+- List the steps in the order they should be done
+- Between 3 and 8 steps, each one small enough to finish in a day
+- Don't repeat the original todo as a step"#;
+
+// Subtasks inherit the topic, owner, priority and due date of their parent
+fn subtask_for(
+    parent: &Todo,
+    step: &Step,
+    position: usize,
+    total: usize,
+) -> Result<Todo, Box<dyn Error>> {
+    let mut desc = format!(
+        "Step {} of {} for #{}: {}",
+        position, total, parent.id, parent.text
+    );
+    if let Some(estimate) = &step.estimate {
+        desc.push_str(&format!("\nEstimate: {}", estimate));
+    }
+
+    let mut subtask = build_todo(
+        step.text.clone(),
+        Some(parent.topic.clone()),
+        Some(parent.priority.clone()),
+        Some(parent.owner.clone()),
+        Some(parent.due.clone()),
+        Some(desc),
+    )?;
+    subtask.parent_id = Some(parent.id);
+    Ok(subtask)
+}
+
+// Turn "1,3-5" / "all" into zero-based indexes
+fn parse_selection(input: &str, len: usize) -> Result<Vec<usize>, Box<dyn Error>> {
+    let input = input.trim().to_lowercase();
+    if input.is_empty() || input == "none" {
+        return Ok(Vec::new());
+    }
+    if input == "all" || input == "a" {
+        return Ok((0..len).collect());
+    }
+
+    let mut picked = Vec::new();
+    for part in input.split([',', ' ']).filter(|p| !p.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.trim().parse::<usize>()?, end.trim().parse::<usize>()?),
+            None => {
+                let n = part.parse::<usize>()?;
+                (n, n)
+            }
+        };
+
+        if start == 0 || end > len || start > end {
+            return Err(format!("'{}' is not a step between 1 and {}", part, len).into());
+        }
+        for n in start..=end {
+            if !picked.contains(&(n - 1)) {
+                picked.push(n - 1);
+            }
+        }
+    }
+    picked.sort();
+    Ok(picked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nothing_is_picked_for_an_empty_answer() {
+        assert!(parse_selection("", 5).unwrap().is_empty());
+        assert!(parse_selection("  \n", 5).unwrap().is_empty());
+        assert!(parse_selection("none", 5).unwrap().is_empty());
+    }
+
+    #[test]
+    fn all_picks_every_step() {
+        assert_eq!(parse_selection("all", 3).unwrap(), [0, 1, 2]);
+        assert_eq!(parse_selection(" A\n", 3).unwrap(), [0, 1, 2]);
+    }
+
+    #[test]
+    fn lists_and_ranges_are_combined_in_order() {
+        assert_eq!(parse_selection("1,3-5", 6).unwrap(), [0, 2, 3, 4]);
+        assert_eq!(parse_selection("4 2, 1", 6).unwrap(), [0, 1, 3]);
+        assert_eq!(parse_selection("2-3,3,2", 6).unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn steps_out_of_range_are_rejected() {
+        assert!(parse_selection("0", 3).is_err());
+        assert!(parse_selection("4", 3).is_err());
+        assert!(parse_selection("2-4", 3).is_err());
+        assert!(parse_selection("3-1", 3).is_err());
+    }
+
+    #[test]
+    fn anything_else_is_rejected() {
+        assert!(parse_selection("first", 3).is_err());
+        assert!(parse_selection("1-", 3).is_err());
+        assert!(parse_selection("-2", 3).is_err());
+    }
+}
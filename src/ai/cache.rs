@@ -0,0 +1,125 @@
+use async_trait::async_trait;
+use chrono::Local;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::time::Duration;
+
+use super::provider::{CompletionRequest, CompletionResponse, LlmProvider, OnToken, Usage};
+use crate::database::DBtodo;
+
+/// Answers repeated requests from the local cache and records the token usage of every call.
+/// Requests with tools aren't cached, their answers lead to actions.
+pub struct Cached {
+    inner: Box<dyn LlmProvider>,
+    // None when caching is turned off, usage is still recorded
+    ttl: Option<Duration>,
+}
+
+impl Cached {
+    pub fn new(inner: Box<dyn LlmProvider>, ttl: Option<Duration>) -> Self {
+        Self { inner, ttl }
+    }
+
+    // A hash of the request exactly as it would be sent, so any change to the prompt or the
+    // todos is a miss and the todos themselves aren't kept in the cache
+    fn key(&self, request: &CompletionRequest) -> Option<String> {
+        if self.ttl.is_none() || !request.tools.is_empty() {
+            return None;
+        }
+        let digest = Sha256::digest(self.inner.preview(request, false).ok()?.as_bytes());
+        Some(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+    }
+
+    // "%Y-%m-%d %H:%M:%S" of the oldest answer still fresh
+    fn fresh_since(&self) -> String {
+        let ttl = chrono::Duration::from_std(self.ttl.unwrap_or_default()).unwrap_or_default();
+        (Local::now() - ttl).format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+
+    fn lookup(&self, key: &str) -> Option<String> {
+        let text = DBtodo::new()
+            .ok()?
+            .get_cached_response(key, &self.fresh_since())
+            .ok()??;
+        self.record(None, true);
+        Some(text)
+    }
+
+    // Bookkeeping failures shouldn't cost the user an answer they already paid for
+    fn store(&self, key: Option<String>, response: &CompletionResponse) {
+        self.record(response.usage, false);
+        if let Some(key) = key
+            && response.tool_calls.is_empty()
+            && let Ok(db) = DBtodo::new()
+        {
+            let _ = db.cache_response(&key, &response.text, &self.fresh_since());
+        }
+    }
+
+    fn record(&self, usage: Option<Usage>, cached: bool) {
+        if let Ok(db) = DBtodo::new() {
+            let tokens = usage.map(|usage| (usage.prompt_tokens, usage.completion_tokens));
+            let _ = db.record_ai_usage(self.inner.model(), tokens, cached);
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Cached {
+    async fn complete(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let key = self.key(request);
+        if let Some(text) = key.as_deref().and_then(|key| self.lookup(key)) {
+            return Ok(CompletionResponse {
+                text,
+                ..Default::default()
+            });
+        }
+
+        let response = self.inner.complete(request).await?;
+        self.store(key, &response);
+        Ok(response)
+    }
+
+    async fn stream(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut OnToken<'_>,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let key = self.key(request);
+        if let Some(text) = key.as_deref().and_then(|key| self.lookup(key)) {
+            on_token(&text);
+            return Ok(CompletionResponse {
+                text,
+                ..Default::default()
+            });
+        }
+
+        let response = self.inner.stream(request, on_token).await?;
+        self.store(key, &response);
+        Ok(response)
+    }
+
+    fn preview(&self, request: &CompletionRequest, stream: bool) -> Result<String, Box<dyn Error>> {
+        self.inner.preview(request, stream)
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    // Counted as a call, embedding endpoints don't all report tokens
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
+        let vectors = self.inner.embed(texts).await?;
+        if let Ok(db) = DBtodo::new() {
+            let _ = db.record_ai_usage(self.inner.embedding_model(), None, false);
+        }
+        Ok(vectors)
+    }
+
+    fn embedding_model(&self) -> &str {
+        self.inner.embedding_model()
+    }
+}
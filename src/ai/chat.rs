@@ -0,0 +1,280 @@
+use colored::*;
+use std::error::Error;
+use std::io::{self, Write};
+
+use super::context::TodoContext;
+use super::privacy::Privacy;
+use super::provider::{CompletionRequest, LlmProvider, Message, Role};
+use crate::database::DBtodo;
+
+// PRINT THE SAVED CONVERSATIONS
+pub fn list_conversations() -> Result<(), Box<dyn Error>> {
+    let db = DBtodo::new()?;
+    let conversations = db.get_conversations()?;
+
+    if conversations.is_empty() {
+        println!("No saved conversations. Use /save inside `rustydo ai chat` to keep one.");
+        return Ok(());
+    }
+
+    println!();
+    for conversation in conversations {
+        println!(
+            "  {:>4}  {}  {}  {}",
+            conversation.id.to_string().yellow().bold(),
+            conversation.updated_at.bright_black(),
+            conversation.title,
+            format!("({} messages)", conversation.messages).bright_black()
+        );
+    }
+    println!();
+    println!("Resume one with `rustydo ai chat --resume <ID>`");
+    Ok(())
+}
+
+struct ChatSession {
+    db: DBtodo,
+    client: Box<dyn LlmProvider>,
+    // Set once the conversation is saved, new messages are then stored as they come
+    conversation_id: Option<i64>,
+    history: Vec<Message>,
+    // The todo list as the model last saw it
+    todos_seen: String,
+}
+
+// INTERACTIVE CHAT ABOUT THE TODOS
+pub async fn chat(resume: Option<i64>, show_request: bool) -> Result<(), Box<dyn Error>> {
+    let mut session = ChatSession {
+        db: DBtodo::new()?,
+        client: super::client(show_request)?,
+        conversation_id: None,
+        history: Vec::new(),
+        todos_seen: String::new(),
+    };
+
+    if let Some(id) = resume {
+        session.resume(id)?;
+    }
+
+    println!();
+    println!(
+        "{} {}",
+        "💬 Chat about your todos.".bold(),
+        "Commands: /todos /clear /save [title] /exit".bright_black()
+    );
+    println!();
+
+    loop {
+        print!("{} ", "you ›".green().bold());
+        io::stdout().flush()?;
+
+        // Ctrl-D ends the session
+        let Some(line) = session.read_line().await? else {
+            println!();
+            break;
+        };
+
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        match line.split_once(' ').map_or(line, |(command, _)| command) {
+            "/exit" | "/quit" => break,
+            "/todos" => session.print_todos()?,
+            "/clear" => {
+                session.history.clear();
+                session.conversation_id = None;
+                session.todos_seen.clear();
+                println!("🧹 Started a new conversation");
+            }
+            "/save" => {
+                let title = line.strip_prefix("/save").unwrap_or("").trim();
+                session.save(title)?;
+            }
+            command if command.starts_with('/') => {
+                println!("Unknown command {}", command);
+            }
+            _ => match session.send(line).await {
+                Ok(()) => {}
+                Err(e) if e.is::<super::privacy::RequestShown>() => println!("\n{}", e),
+                Err(e) => eprintln!("Error: {}", e),
+            },
+        }
+    }
+
+    session.warn_unsaved();
+    Ok(())
+}
+
+impl ChatSession {
+    fn resume(&mut self, id: i64) -> Result<(), Box<dyn Error>> {
+        let messages = self
+            .db
+            .get_chat_messages(id)?
+            .ok_or(format!("No conversation found with id: {}", id))?;
+
+        for message in messages {
+            println!("{} {}", role_label(&message.role), message.content);
+            self.history.push(match message.role.as_str() {
+                "assistant" => Message::assistant(message.content, Vec::new()),
+                _ => Message::user(message.content),
+            });
+        }
+        self.conversation_id = Some(id);
+        Ok(())
+    }
+
+    async fn send(&mut self, text: &str) -> Result<(), Box<dyn Error>> {
+        let todos = Privacy::load()?.todos(&self.db.get_todos()?);
+        let todos_now = super::format_todos(&todos);
+
+        // The system prompt always carries the latest list, tell the model when it moved
+        let mut content = text.to_string();
+        if !self.todos_seen.is_empty() && self.todos_seen != todos_now {
+            println!(
+                "{}",
+                "📋 Your todos changed, sharing the latest list".bright_black()
+            );
+            content = format!(
+                "(My todo list has changed since my last message, see CURRENT TODOS.)\n{}",
+                text
+            );
+        }
+
+        let context = TodoContext::load()?.todos(&todos, text);
+        let mut messages = vec![Message::system(super::assistant_prompt(&context))];
+        messages.extend(self.history.iter().cloned());
+        messages.push(Message::user(content.clone()));
+
+        let request = CompletionRequest {
+            messages,
+            ..Default::default()
+        };
+        let mut print_token = |token: &str| {
+            print!("{}", token);
+            let _ = io::stdout().flush();
+        };
+
+        println!();
+        print!("🤖 ");
+        // Ctrl-C drops the answer but keeps the chat going
+        let response = tokio::select! {
+            response = self.client.stream(&request, &mut print_token) => response?,
+            _ = tokio::signal::ctrl_c() => {
+                println!("\n\n⏹  Cancelled");
+                println!();
+                return Ok(());
+            }
+        };
+        println!();
+        println!();
+
+        self.todos_seen = todos_now;
+        self.push(Message::user(content))?;
+        self.push(Message::assistant(response.text, Vec::new()))?;
+        Ok(())
+    }
+
+    // Read a line without blocking Ctrl-C, None at the end of input
+    async fn read_line(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let read = tokio::task::spawn_blocking(|| {
+            let mut line = String::new();
+            io::stdin()
+                .read_line(&mut line)
+                .map(|n| (n > 0).then_some(line))
+        });
+
+        tokio::select! {
+            line = read => Ok(line??),
+            _ = tokio::signal::ctrl_c() => {
+                println!();
+                self.warn_unsaved();
+                // The pending stdin read can't be cancelled, so leave right away
+                std::process::exit(0);
+            }
+        }
+    }
+
+    fn warn_unsaved(&self) {
+        if self.conversation_id.is_none() && !self.history.is_empty() {
+            println!("This conversation wasn't saved. Use /save next time to keep it.");
+        }
+    }
+
+    fn push(&mut self, message: Message) -> Result<(), Box<dyn Error>> {
+        if let Some(id) = self.conversation_id {
+            self.db
+                .add_chat_message(id, role_name(message.role), &message.content)?;
+        }
+        self.history.push(message);
+        Ok(())
+    }
+
+    fn save(&mut self, title: &str) -> Result<(), Box<dyn Error>> {
+        if let Some(id) = self.conversation_id {
+            println!(
+                "💾 Already saved as conversation {}, new messages are saved as you go",
+                id
+            );
+            return Ok(());
+        }
+
+        // Default the title to the start of the first question
+        let title = if title.is_empty() {
+            self.history
+                .iter()
+                .find(|m| m.role == Role::User)
+                .map(|m| {
+                    m.content
+                        .lines()
+                        .last()
+                        .unwrap_or("")
+                        .chars()
+                        .take(50)
+                        .collect()
+                })
+                .unwrap_or_else(|| "Untitled chat".to_string())
+        } else {
+            title.to_string()
+        };
+
+        let id = self.db.create_conversation(&title)?;
+        for message in &self.history {
+            self.db
+                .add_chat_message(id, role_name(message.role), &message.content)?;
+        }
+        self.conversation_id = Some(id);
+        println!(
+            "💾 Saved as conversation {}, resume it with `rustydo ai chat --resume {}`",
+            id, id
+        );
+        Ok(())
+    }
+
+    fn print_todos(&self) -> Result<(), Box<dyn Error>> {
+        let todos = self.db.get_todos()?;
+        println!();
+        if todos.is_empty() {
+            println!("No todos yet.");
+        } else {
+            println!("{}", super::format_todos(&todos));
+        }
+        println!();
+        Ok(())
+    }
+}
+
+fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::Assistant => "assistant",
+        _ => "user",
+    }
+}
+
+fn role_label(role: &str) -> ColoredString {
+    match role {
+        "assistant" => "🤖".normal(),
+        _ => "you ›".green().bold(),
+    }
+}
@@ -0,0 +1,323 @@
+use chrono::{Local, NaiveDate};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+use crate::arguments::models::Todo;
+use crate::configs::AppConfigs;
+
+// Words too common to say anything about which todos a prompt is about
+const STOP_WORDS: [&str; 24] = [
+    "the", "and", "for", "with", "what", "which", "should", "could", "would", "about", "from",
+    "that", "this", "have", "has", "are", "was", "were", "you", "your", "my", "mine", "todo",
+    "todos",
+];
+
+// Longest the "more todos not shown" line gets
+const SUMMARY_TOKENS: usize = 40;
+
+/// Rough token count, about four characters per token for English text
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(4)
+}
+
+/// Fits a todo list into the `[AI] context_tokens` budget, most relevant todos first
+pub struct TodoContext {
+    budget: usize,
+}
+
+impl TodoContext {
+    pub fn new(budget: usize) -> Self {
+        Self { budget }
+    }
+
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        Ok(Self::new(AppConfigs::new()?.context_tokens))
+    }
+
+    /// All todos, for prompts about the whole list
+    pub fn todos(&self, todos: &[Todo], query: &str) -> String {
+        self.fit(todos, query, super::format_todo)
+    }
+
+    /// Open todos with the details needed to rank them
+    pub fn open_todos(&self, todos: &[Todo], query: &str) -> String {
+        let open = todos
+            .iter()
+            .filter(|todo| todo.status != "Done")
+            .cloned()
+            .collect::<Vec<Todo>>();
+        self.fit(&open, query, super::format_open_todo)
+    }
+
+    /// Todos written with `line`, for prompts that need their own format
+    pub fn lines(&self, todos: &[Todo], query: &str, line: fn(&Todo) -> String) -> String {
+        self.fit(todos, query, line)
+    }
+
+    /// The todos that would be shown, in list order, for prompts built from them some other way
+    pub fn relevant(&self, todos: &[Todo], query: &str) -> Vec<Todo> {
+        let (mut shown, _) = self.pick(todos, query, super::format_todo);
+        shown.sort_by_key(|(position, _)| *position);
+        shown
+            .into_iter()
+            .map(|(position, _)| todos[position].clone())
+            .collect()
+    }
+
+    // Take todos by relevance until the budget runs out, then count what was left out
+    fn fit(&self, todos: &[Todo], query: &str, line: fn(&Todo) -> String) -> String {
+        let (mut shown, left_out) = self.pick(todos, query, line);
+
+        // Back in list order so the model reads them as the user keeps them
+        shown.sort_by_key(|(position, _)| *position);
+        let mut lines = shown
+            .into_iter()
+            .map(|(_, text)| text)
+            .collect::<Vec<String>>();
+        if !left_out.is_empty() {
+            lines.push(summary(&left_out));
+        }
+        lines.join("\n")
+    }
+
+    // The (position, line) of each todo that fits, most relevant first, and the todos left out
+    fn pick<'a>(
+        &self,
+        todos: &'a [Todo],
+        query: &str,
+        line: fn(&Todo) -> String,
+    ) -> (Vec<(usize, String)>, Vec<&'a Todo>) {
+        let today = Local::now().date_naive();
+        let keywords = keywords(query);
+
+        let mut ranked = todos.iter().enumerate().collect::<Vec<(usize, &Todo)>>();
+        ranked.sort_by_key(|(position, todo)| {
+            (
+                std::cmp::Reverse(relevance(todo, &keywords, today)),
+                *position,
+            )
+        });
+
+        // Room for the summary line is kept back as soon as something has to go
+        let total = todos
+            .iter()
+            .map(|todo| estimate_tokens(&line(todo)) + 1)
+            .sum::<usize>();
+        let reserve = if total > self.budget {
+            SUMMARY_TOKENS
+        } else {
+            0
+        };
+
+        let mut used = 0;
+        let mut shown = Vec::new();
+        let mut left_out = Vec::new();
+        for (position, todo) in ranked {
+            let text = line(todo);
+            let cost = estimate_tokens(&text) + 1;
+            if left_out.is_empty() && used + cost + reserve <= self.budget {
+                used += cost;
+                shown.push((position, text));
+            } else {
+                left_out.push(todo);
+            }
+        }
+        (shown, left_out)
+    }
+}
+
+// Lower-cased words of the prompt worth matching against todos
+fn keywords(query: &str) -> Vec<String> {
+    query
+        .split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.chars().count() >= 3 && !STOP_WORDS.contains(&word.as_str()))
+        .collect()
+}
+
+// Higher is more worth showing: open, urgent, important and about what was asked
+fn relevance(todo: &Todo, keywords: &[String], today: NaiveDate) -> u32 {
+    if todo.status == "Done" {
+        return keyword_score(todo, keywords);
+    }
+
+    let mut score = 10;
+    score += match todo.priority.as_str() {
+        "High" => 6,
+        "Medium" => 4,
+        "Normal" => 2,
+        _ => 0,
+    };
+    if todo.status == "Ongoing" {
+        score += 3;
+    }
+    if let Ok(due) = NaiveDate::parse_from_str(&todo.due, "%Y-%m-%d") {
+        score += match (due - today).num_days() {
+            ..0 => 8,
+            0..=2 => 6,
+            3..=7 => 3,
+            _ => 0,
+        };
+    }
+    score + keyword_score(todo, keywords)
+}
+
+fn keyword_score(todo: &Todo, keywords: &[String]) -> u32 {
+    let haystack =
+        format!("{} {} {} {}", todo.text, todo.desc, todo.topic, todo.owner).to_lowercase();
+    let matches = keywords
+        .iter()
+        .filter(|keyword| haystack.contains(keyword.as_str()))
+        .count() as u32;
+    matches.min(3) * 8
+}
+
+// "- 1200 more todos not shown (1150 done, 50 open: 30 Low, 20 Normal)"
+// or "- 50 more open todos not shown (30 Low, 20 Normal)"
+fn summary(left_out: &[&Todo]) -> String {
+    let open = left_out
+        .iter()
+        .filter(|todo| todo.status != "Done")
+        .collect::<Vec<&&Todo>>();
+
+    let mut by_priority: BTreeMap<&str, usize> = BTreeMap::new();
+    for todo in &open {
+        *by_priority.entry(todo.priority.as_str()).or_default() += 1;
+    }
+    let priorities = by_priority
+        .iter()
+        .map(|(priority, count)| format!("{} {}", count, priority))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    let done = left_out.len() - open.len();
+    if done == 0 {
+        return format!(
+            "- {} more open todos not shown ({})",
+            open.len(),
+            priorities
+        );
+    }
+    format!(
+        "- {} more todos not shown ({} done, {} open{})",
+        left_out.len(),
+        done,
+        open.len(),
+        if priorities.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", priorities)
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Long enough that a handful of todos doesn't fit next to the summary line
+    fn todo(id: usize, text: &str, priority: &str, status: &str) -> Todo {
+        Todo {
+            id,
+            priority: priority.to_string(),
+            topic: "Work".to_string(),
+            text: format!("{} {}", text, "with some notes".repeat(10)),
+            desc: String::new(),
+            date_added: "01-10-26".to_string(),
+            status: status.to_string(),
+            owner: "Alex".to_string(),
+            due: "-".to_string(),
+            parent_id: None,
+        }
+    }
+
+    // Room for `count` of the todos' lines and the summary line
+    fn room_for(count: usize, todos: &[Todo]) -> TodoContext {
+        let line = todos
+            .iter()
+            .map(|todo| estimate_tokens(&super::super::format_todo(todo)) + 1)
+            .max()
+            .unwrap();
+        TodoContext::new(SUMMARY_TOKENS + count * line)
+    }
+
+    fn shown(context: &str) -> Vec<String> {
+        context
+            .lines()
+            .map(|line| line.split(" with some notes").next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn everything_is_sent_when_it_fits() {
+        let todos = [
+            todo(1, "Write the report", "Low", "Done"),
+            todo(2, "Book the venue", "High", "Pending"),
+        ];
+        let context = TodoContext::new(1000).todos(&todos, "");
+        assert_eq!(
+            shown(&context),
+            ["- [x] Write the report", "- [ ] Book the venue"]
+        );
+    }
+
+    #[test]
+    fn the_most_relevant_todos_fit_and_the_rest_are_counted() {
+        let todos = [
+            todo(1, "Write the report", "Low", "Done"),
+            todo(2, "Book the venue", "High", "Pending"),
+            todo(3, "Order the badges", "Normal", "Pending"),
+            todo(4, "Send the invites", "Low", "Done"),
+        ];
+        let context = room_for(2, &todos).todos(&todos, "");
+        // Open todos first, shown in list order
+        assert_eq!(
+            shown(&context),
+            [
+                "- [ ] Book the venue",
+                "- [ ] Order the badges",
+                "- 2 more todos not shown (2 done, 0 open)"
+            ]
+        );
+    }
+
+    #[test]
+    fn the_summary_counts_open_todos_by_priority() {
+        let todos = [
+            todo(1, "Book the venue", "High", "Pending"),
+            todo(2, "Order the badges", "Normal", "Pending"),
+            todo(3, "Print the signs", "Low", "Pending"),
+            todo(4, "Pack the boxes", "Low", "Ongoing"),
+        ];
+        let context = room_for(1, &todos).todos(&todos, "");
+        assert_eq!(
+            shown(&context),
+            [
+                "- [ ] Book the venue",
+                "- 3 more open todos not shown (2 Low, 1 Normal)"
+            ]
+        );
+    }
+
+    #[test]
+    fn todos_about_the_prompt_come_before_urgent_ones() {
+        let todos = [
+            todo(1, "Book the venue", "High", "Pending"),
+            todo(2, "Send the invoice to the client", "Low", "Pending"),
+        ];
+        let context = room_for(1, &todos);
+        assert_eq!(shown(&context.todos(&todos, ""))[0], "- [ ] Book the venue");
+        assert_eq!(
+            shown(&context.todos(&todos, "when is the invoice due?"))[0],
+            "- [ ] Send the invoice to the client"
+        );
+    }
+
+    #[test]
+    fn keywords_skip_short_and_common_words() {
+        assert_eq!(
+            keywords("What should I do about the Q3 invoice?"),
+            ["invoice"]
+        );
+    }
+}
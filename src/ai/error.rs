@@ -0,0 +1,244 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/// Everything that can go wrong talking to an AI provider
+#[derive(Debug)]
+pub enum AiError {
+    /// 401/403, the key is missing, wrong or lacks access
+    Auth(String),
+    /// 429, rate limited or out of quota
+    Quota {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// The server couldn't be reached
+    Network(String),
+    /// No answer within `timeout_secs`
+    Timeout,
+    /// 5xx from the provider
+    Server { status: u16, message: String },
+    /// Any other 4xx, e.g. an unknown model
+    Rejected { status: u16, message: String },
+    /// An error reported inside an otherwise successful response
+    Provider(String),
+    /// The provider's safety filters blocked the prompt or the answer
+    SafetyBlock(String),
+    /// A response that doesn't have the expected shape
+    Malformed(String),
+}
+
+impl AiError {
+    /// Worth trying again after a pause
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, AiError::Quota { .. } | AiError::Server { .. })
+    }
+
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            AiError::Quota { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    // Map a failed HTTP status and its body to an error
+    fn from_status(status: u16, body: &str, retry_after: Option<Duration>) -> Self {
+        let message = error_message(body);
+        match status {
+            401 | 403 => AiError::Auth(message),
+            429 => AiError::Quota {
+                message,
+                retry_after,
+            },
+            500..=599 => AiError::Server { status, message },
+            _ => AiError::Rejected { status, message },
+        }
+    }
+}
+
+impl fmt::Display for AiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AiError::Auth(message) => write!(
+                f,
+                "The AI provider rejected the API key ({}). Check the key with `rustydo --apikey-stdin` or the [AI] api_key settings",
+                message
+            ),
+            AiError::Quota { message, .. } => {
+                write!(
+                    f,
+                    "Rate limit or quota exceeded, try again later ({})",
+                    message
+                )
+            }
+            AiError::Network(message) => write!(f, "Couldn't reach the AI provider: {}", message),
+            AiError::Timeout => write!(
+                f,
+                "The AI provider didn't answer in time. Raise timeout_secs in the [AI] config for slow models"
+            ),
+            AiError::Server { status, message } => {
+                write!(f, "The AI provider failed ({}): {}", status, message)
+            }
+            AiError::Rejected { status, message } => {
+                write!(
+                    f,
+                    "The AI provider rejected the request ({}): {}",
+                    status, message
+                )
+            }
+            AiError::Provider(message) => {
+                write!(f, "The AI provider returned an error: {}", message)
+            }
+            AiError::SafetyBlock(reason) => {
+                write!(
+                    f,
+                    "Blocked by the AI provider's safety filters ({})",
+                    reason
+                )
+            }
+            AiError::Malformed(message) => {
+                write!(f, "Unexpected response from the AI provider: {}", message)
+            }
+        }
+    }
+}
+
+impl Error for AiError {}
+
+impl From<reqwest::Error> for AiError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            return AiError::Timeout;
+        }
+        // reqwest reports a body cut off mid-way as a decode error too, only bad JSON is malformed
+        if e.is_decode() && is_json_error(&e) {
+            return AiError::Malformed(root_cause(&e));
+        }
+        if let Some(status) = e.status() {
+            return AiError::from_status(status.as_u16(), "", None);
+        }
+        AiError::Network(root_cause(&e))
+    }
+}
+
+impl From<serde_json::Error> for AiError {
+    fn from(e: serde_json::Error) -> Self {
+        AiError::Malformed(e.to_string())
+    }
+}
+
+// SEND A REQUEST AND TURN HTTP FAILURES INTO TYPED ERRORS
+pub async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, AiError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(Duration::from_secs);
+    let body = response.text().await.unwrap_or_default();
+    Err(AiError::from_status(status.as_u16(), &body, retry_after))
+}
+
+// Providers wrap errors as {"error": {"message": ...}} (OpenAI, Gemini) or {"error": "..."} (Ollama)
+fn error_message(body: &str) -> String {
+    let message = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| {
+            let error = json.get("error")?;
+            error
+                .get("message")
+                .and_then(|m| m.as_str())
+                .or_else(|| error.as_str())
+                .map(str::to_string)
+        })
+        .unwrap_or_else(|| body.trim().chars().take(200).collect());
+
+    if message.is_empty() {
+        "no details".to_string()
+    } else {
+        message
+    }
+}
+
+// "error sending request" alone doesn't say much, the innermost cause does
+fn is_json_error(e: &reqwest::Error) -> bool {
+    let mut cause: Option<&dyn Error> = e.source();
+    while let Some(error) = cause {
+        if error.is::<serde_json::Error>() {
+            return true;
+        }
+        cause = error.source();
+    }
+    false
+}
+
+fn root_cause(e: &reqwest::Error) -> String {
+    let mut cause: &dyn Error = e;
+    while let Some(source) = cause.source() {
+        cause = source;
+    }
+    match e.url() {
+        Some(url) => format!("{} ({})", cause, url),
+        None => cause.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn statuses_are_classified() {
+        assert!(matches!(
+            AiError::from_status(401, "", None),
+            AiError::Auth(_)
+        ));
+        assert!(matches!(
+            AiError::from_status(403, "", None),
+            AiError::Auth(_)
+        ));
+        assert!(matches!(
+            AiError::from_status(429, "", Some(Duration::from_secs(7))),
+            AiError::Quota { retry_after: Some(delay), .. } if delay == Duration::from_secs(7)
+        ));
+        assert!(matches!(
+            AiError::from_status(503, "", None),
+            AiError::Server { status: 503, .. }
+        ));
+        assert!(matches!(
+            AiError::from_status(404, "", None),
+            AiError::Rejected { status: 404, .. }
+        ));
+    }
+
+    #[test]
+    fn only_rate_limits_and_server_errors_are_retried() {
+        assert!(AiError::from_status(429, "", None).is_retryable());
+        assert!(AiError::from_status(500, "", None).is_retryable());
+        assert!(!AiError::from_status(401, "", None).is_retryable());
+        assert!(!AiError::from_status(400, "", None).is_retryable());
+        assert!(!AiError::Timeout.is_retryable());
+        assert!(!AiError::Malformed(String::new()).is_retryable());
+    }
+
+    #[test]
+    fn messages_are_read_from_every_error_shape() {
+        assert_eq!(
+            error_message(r#"{"error": {"message": "model not found", "code": 404}}"#),
+            "model not found"
+        );
+        assert_eq!(
+            error_message(r#"{"error": "model not found"}"#),
+            "model not found"
+        );
+        assert_eq!(error_message("  Bad Gateway \n"), "Bad Gateway");
+        assert_eq!(error_message(&"x".repeat(500)).len(), 200);
+        assert_eq!(error_message(""), "no details");
+    }
+}
@@ -0,0 +1,541 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+use super::error::{AiError, send};
+use super::provider::{
+    CompletionRequest, CompletionResponse, LlmProvider, Message, OnToken, Role, ToolCall, Usage,
+    format_preview, read_lines,
+};
+
+const DEFAULT_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+const DEFAULT_MODEL: &str = "gemini-2.0-flash";
+const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-004";
+
+#[derive(Serialize)]
+struct Content {
+    parts: Vec<Part>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    role: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+struct Part {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_call: Option<FunctionCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    function_response: Option<FunctionResponse>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct FunctionCall {
+    name: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct FunctionResponse {
+    name: String,
+    response: serde_json::Value,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FunctionDeclaration {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Tools {
+    function_declarations: Vec<FunctionDeclaration>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GenerationConfig {
+    response_mime_type: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RequestBody {
+    contents: Vec<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generation_config: Option<GenerationConfig>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<Tools>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct Candidate {
+    // Missing when the answer was blocked
+    #[serde(default)]
+    content: CandidateContent,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct CandidateContent {
+    #[serde(default)]
+    parts: Vec<Part>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct PromptFeedback {
+    #[serde(default)]
+    block_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GeminiResponse {
+    #[serde(default)]
+    candidates: Vec<Candidate>,
+    #[serde(default)]
+    prompt_feedback: Option<PromptFeedback>,
+    #[serde(default)]
+    usage_metadata: Option<UsageMetadata>,
+}
+
+#[derive(Serialize)]
+struct EmbedRequest {
+    model: String,
+    content: Content,
+}
+
+#[derive(Serialize)]
+struct BatchEmbedBody {
+    requests: Vec<EmbedRequest>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ContentEmbedding {
+    #[serde(default)]
+    values: Vec<f32>,
+}
+
+#[derive(Deserialize, Debug)]
+struct BatchEmbedResponse {
+    #[serde(default)]
+    embeddings: Vec<ContentEmbedding>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct UsageMetadata {
+    #[serde(default)]
+    prompt_token_count: u64,
+    #[serde(default)]
+    candidates_token_count: u64,
+}
+
+impl From<UsageMetadata> for Usage {
+    fn from(usage: UsageMetadata) -> Self {
+        Usage {
+            prompt_tokens: usage.prompt_token_count,
+            completion_tokens: usage.candidates_token_count,
+        }
+    }
+}
+
+// Finish reasons that mean the answer was withheld
+const BLOCKED: [&str; 5] = [
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+];
+
+impl GeminiResponse {
+    // The first candidate, None for stream chunks that only carry metadata
+    fn candidate(self) -> Result<Option<Candidate>, AiError> {
+        if let Some(reason) = self.prompt_feedback.and_then(|f| f.block_reason) {
+            return Err(AiError::SafetyBlock(format!("prompt blocked: {}", reason)));
+        }
+        let candidate = self.candidates.into_iter().next();
+        if let Some(reason) = candidate.as_ref().and_then(|c| c.finish_reason.as_deref())
+            && BLOCKED.contains(&reason)
+        {
+            return Err(AiError::SafetyBlock(format!("answer blocked: {}", reason)));
+        }
+        Ok(candidate)
+    }
+}
+
+pub struct Gemini {
+    client: Client,
+    base_url: String,
+    model: String,
+    embedding_model: String,
+    api_key: String,
+}
+
+impl Gemini {
+    pub fn new(
+        client: Client,
+        base_url: Option<String>,
+        model: Option<String>,
+        embedding_model: Option<String>,
+        api_key: String,
+    ) -> Self {
+        Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            embedding_model: embedding_model.unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.to_string()),
+            api_key,
+        }
+    }
+
+    fn url(&self, stream: bool) -> String {
+        if stream {
+            format!(
+                "{}/models/{}:streamGenerateContent?alt=sse",
+                self.base_url, self.model
+            )
+        } else {
+            format!("{}/models/{}:generateContent", self.base_url, self.model)
+        }
+    }
+
+    fn body(&self, request: &CompletionRequest) -> RequestBody {
+        // Gemini takes system prompts separately from the conversation
+        let system = request
+            .messages
+            .iter()
+            .filter(|m| m.role == Role::System)
+            .map(|m| Part {
+                text: Some(m.content.clone()),
+                ..Default::default()
+            })
+            .collect::<Vec<Part>>();
+
+        let contents = request
+            .messages
+            .iter()
+            .filter(|m| m.role != Role::System)
+            .map(|m| Content {
+                role: Some(match m.role {
+                    Role::Assistant => "model".to_string(),
+                    _ => "user".to_string(),
+                }),
+                parts: parts_for(m),
+            })
+            .collect();
+
+        let tools = if request.tools.is_empty() {
+            Vec::new()
+        } else {
+            vec![Tools {
+                function_declarations: request
+                    .tools
+                    .iter()
+                    .map(|tool| FunctionDeclaration {
+                        name: tool.name.to_string(),
+                        description: tool.description.to_string(),
+                        parameters: tool.parameters.clone(),
+                    })
+                    .collect(),
+            }]
+        };
+
+        RequestBody {
+            contents,
+            system_instruction: (!system.is_empty()).then_some(Content {
+                parts: system,
+                role: None,
+            }),
+            generation_config: request.json.then(|| GenerationConfig {
+                response_mime_type: "application/json".to_string(),
+            }),
+            tools,
+        }
+    }
+}
+
+fn parts_for(message: &Message) -> Vec<Part> {
+    if let Some(call) = &message.tool_call {
+        return vec![Part {
+            function_response: Some(FunctionResponse {
+                name: call.name.clone(),
+                response: serde_json::json!({ "content": message.content }),
+            }),
+            ..Default::default()
+        }];
+    }
+
+    let mut parts = Vec::new();
+    if !message.content.is_empty() {
+        parts.push(Part {
+            text: Some(message.content.clone()),
+            ..Default::default()
+        });
+    }
+    for call in &message.tool_calls {
+        parts.push(Part {
+            function_call: Some(FunctionCall {
+                name: call.name.clone(),
+                args: call.arguments.clone(),
+            }),
+            ..Default::default()
+        });
+    }
+    parts
+}
+
+#[async_trait]
+impl LlmProvider for Gemini {
+    async fn complete(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let url = self.url(false);
+
+        let mut response = send(
+            self.client
+                .post(&url)
+                .header("x-goog-api-key", &self.api_key)
+                .json(&self.body(request)),
+        )
+        .await?
+        .json::<GeminiResponse>()
+        .await
+        .map_err(AiError::from)?;
+
+        let usage = response.usage_metadata.take().map(Usage::from);
+        let candidate = response
+            .candidate()?
+            .ok_or_else(|| AiError::Malformed("Gemini returned no candidates".to_string()))?;
+
+        let mut text = String::new();
+        let mut tool_calls = Vec::new();
+        for part in candidate.content.parts {
+            if let Some(part_text) = part.text {
+                text.push_str(&part_text);
+            }
+            // Gemini doesn't give calls an id, number them instead
+            if let Some(call) = part.function_call {
+                tool_calls.push(ToolCall {
+                    id: format!("call_{}", tool_calls.len()),
+                    name: call.name,
+                    arguments: call.args,
+                });
+            }
+        }
+
+        Ok(CompletionResponse {
+            text,
+            tool_calls,
+            usage,
+        })
+    }
+
+    async fn stream(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut OnToken<'_>,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let url = self.url(true);
+
+        let response = send(
+            self.client
+                .post(&url)
+                .header("x-goog-api-key", &self.api_key)
+                .json(&self.body(request)),
+        )
+        .await?;
+
+        // Every SSE event carries a partial GeminiResponse
+        let mut text = String::new();
+        let mut usage = None;
+        let mut candidates = 0;
+        read_lines(response, |line| {
+            let Some(data) = line.strip_prefix("data:") else {
+                return Ok(());
+            };
+            let mut chunk: GeminiResponse =
+                serde_json::from_str(data.trim()).map_err(AiError::from)?;
+            // Running totals, the last chunk has the final count
+            if let Some(reported) = chunk.usage_metadata.take() {
+                usage = Some(Usage::from(reported));
+            }
+            if let Some(candidate) = chunk.candidate()? {
+                candidates += 1;
+                for part in candidate.content.parts {
+                    if let Some(token) = part.text {
+                        on_token(&token);
+                        text.push_str(&token);
+                    }
+                }
+            }
+            Ok(())
+        })
+        .await?;
+        if candidates == 0 {
+            return Err(AiError::Malformed("Gemini returned no candidates".to_string()).into());
+        }
+
+        Ok(CompletionResponse {
+            text,
+            usage,
+            ..Default::default()
+        })
+    }
+
+    fn preview(&self, request: &CompletionRequest, stream: bool) -> Result<String, Box<dyn Error>> {
+        format_preview(&self.url(stream), &self.body(request))
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
+        let url = format!(
+            "{}/models/{}:batchEmbedContents",
+            self.base_url, self.embedding_model
+        );
+        let body = BatchEmbedBody {
+            requests: texts
+                .iter()
+                .map(|text| EmbedRequest {
+                    model: format!("models/{}", self.embedding_model),
+                    content: Content {
+                        parts: vec![Part {
+                            text: Some(text.clone()),
+                            ..Default::default()
+                        }],
+                        role: None,
+                    },
+                })
+                .collect(),
+        };
+        let response = send(
+            self.client
+                .post(&url)
+                .header("x-goog-api-key", &self.api_key)
+                .json(&body),
+        )
+        .await?
+        .json::<BatchEmbedResponse>()
+        .await
+        .map_err(AiError::from)?;
+
+        if response.embeddings.len() != texts.len() {
+            return Err(AiError::Malformed(format!(
+                "expected {} embeddings, got {}",
+                texts.len(),
+                response.embeddings.len()
+            ))
+            .into());
+        }
+        Ok(response
+            .embeddings
+            .into_iter()
+            .map(|embedding| embedding.values)
+            .collect())
+    }
+
+    fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock::{self, MockServer};
+    use crate::ai::provider::Message;
+
+    fn gemini(url: &str) -> Gemini {
+        Gemini::new(
+            mock::client(),
+            Some(url.to_string()),
+            Some("test-model".to_string()),
+            None,
+            "secret".to_string(),
+        )
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            messages: vec![Message::system("Be brief"), Message::user("Hello")],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn a_reply_is_read_from_the_first_candidate() {
+        let server = MockServer::start(vec![mock::json(
+            200,
+            r#"{"candidates":[{"content":{"parts":[{"text":"Hi "},{"text":"there"}]}}]}"#,
+        )])
+        .await;
+        let response = gemini(&server.url).complete(&request()).await.unwrap();
+        assert_eq!(response.text, "Hi there");
+
+        let request = &server.requests().await[0];
+        assert!(request.starts_with("POST /models/test-model:generateContent "));
+        assert!(request.contains("x-goog-api-key: secret"));
+        assert!(request.contains(r#""systemInstruction":{"parts":[{"text":"Be brief"}]}"#));
+    }
+
+    #[tokio::test]
+    async fn a_streamed_reply_is_shown_as_it_arrives() {
+        let server = MockServer::start(vec![mock::stream(
+            "text/event-stream",
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hi \"}]}}]}\r\n\r\n\
+             data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"there\"}]}}]}\r\n\r\n",
+        )])
+        .await;
+        let mut tokens = Vec::new();
+        let mut on_token = |token: &str| tokens.push(token.to_string());
+        let response = gemini(&server.url)
+            .stream(&request(), &mut on_token)
+            .await
+            .unwrap();
+        assert_eq!(response.text, "Hi there");
+        assert_eq!(tokens, ["Hi ", "there"]);
+
+        let request = &server.requests().await[0];
+        assert!(request.starts_with("POST /models/test-model:streamGenerateContent?alt=sse "));
+    }
+
+    #[tokio::test]
+    async fn a_blocked_prompt_is_a_safety_block() {
+        let server = MockServer::start(vec![mock::json(
+            200,
+            r#"{"promptFeedback":{"blockReason":"SAFETY"}}"#,
+        )])
+        .await;
+        let error = gemini(&server.url).complete(&request()).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<AiError>(),
+            Some(AiError::SafetyBlock(reason)) if reason == "prompt blocked: SAFETY"
+        ));
+    }
+
+    #[tokio::test]
+    async fn an_answer_without_candidates_is_malformed() {
+        let server = MockServer::start(vec![mock::json(200, "{}")]).await;
+        let error = gemini(&server.url).complete(&request()).await.unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<AiError>(),
+            Some(AiError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,106 @@
+// A local HTTP server that plays back canned responses, so providers can be tested offline
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+/// Answers one connection per response, in order, and keeps the requests it got
+pub struct MockServer {
+    pub url: String,
+    requests: JoinHandle<Vec<String>>,
+}
+
+impl MockServer {
+    pub async fn start(responses: Vec<String>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for response in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                requests.push(read_request(&mut socket).await);
+                socket.write_all(response.as_bytes()).await.unwrap();
+                let _ = socket.shutdown().await;
+            }
+            requests
+        });
+        Self { url, requests }
+    }
+
+    /// Every request received, once all the responses were sent
+    pub async fn requests(self) -> Vec<String> {
+        self.requests.await.unwrap()
+    }
+}
+
+// The request line, headers and body as text
+async fn read_request(socket: &mut TcpStream) -> String {
+    let mut request = Vec::new();
+    let mut buffer = [0; 4096];
+    loop {
+        let read = socket.read(&mut buffer).await.unwrap();
+        if read == 0 {
+            break;
+        }
+        request.extend_from_slice(&buffer[..read]);
+
+        let text = String::from_utf8_lossy(&request);
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let length = head
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    if !name.eq_ignore_ascii_case("content-length") {
+                        return None;
+                    }
+                    value.trim().parse::<usize>().ok()
+                })
+                .unwrap_or(0);
+            if body.len() >= length {
+                break;
+            }
+        }
+    }
+    String::from_utf8_lossy(&request).into_owned()
+}
+
+/// A client that doesn't go through a proxy set in the environment
+pub fn client() -> reqwest::Client {
+    reqwest::Client::builder().no_proxy().build().unwrap()
+}
+
+/// A complete response with a JSON body
+pub fn json(status: u16, body: &str) -> String {
+    response(status, &[("Content-Type", "application/json")], body)
+}
+
+/// A complete response with any headers
+pub fn response(status: u16, headers: &[(&str, &str)], body: &str) -> String {
+    let mut response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n",
+        status,
+        if status < 400 { "OK" } else { "Error" },
+        body.len()
+    );
+    for (name, value) in headers {
+        response.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    response + "\r\n" + body
+}
+
+/// A streamed response, the body ends when the connection is closed
+pub fn stream(content_type: &str, body: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nConnection: close\r\n\r\n{}",
+        content_type, body
+    )
+}
+
+/// A response whose connection drops after `sent`, before the promised body is complete
+pub fn dropped(content_type: &str, sent: &str) -> String {
+    format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        content_type,
+        sent.len() + 1000,
+        sent
+    )
+}
@@ -0,0 +1,142 @@
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::time::Duration;
+
+use crate::arguments::models::Todo;
+use crate::configs::AppConfigs;
+use crate::database;
+use cache::Cached;
+use context::TodoContext;
+use privacy::{Guarded, Privacy};
+use provider::{CompletionRequest, LlmProvider, Message, OnToken};
+
+pub mod add;
+pub mod agent;
+pub mod breakdown;
+pub mod cache;
+pub mod chat;
+pub mod context;
+pub mod error;
+pub mod gemini;
+#[cfg(test)]
+mod mock;
+pub mod ollama;
+pub mod openai;
+pub mod panel;
+pub mod privacy;
+pub mod provider;
+pub mod similar;
+pub mod standup;
+pub mod triage;
+pub mod usage;
+
+// THE PROVIDER CONFIGURED BY THE USER, BEHIND THE PRIVACY SETTINGS AND THE CACHE.
+// With `show_request` requests are printed instead of sent
+pub fn client(show_request: bool) -> Result<Box<dyn LlmProvider>, Box<dyn Error>> {
+    let configs = AppConfigs::new()?;
+    let provider = Cached::new(
+        provider::from_config(&configs, show_request)?,
+        (configs.cache_ttl_secs > 0).then(|| Duration::from_secs(configs.cache_ttl_secs)),
+    );
+    Ok(Box::new(Guarded::new(
+        Box::new(provider),
+        Privacy::from_config(&configs)?,
+        show_request,
+    )))
+}
+
+// Format todos as text for the prompt
+pub fn format_todos(todos: &[Todo]) -> String {
+    todos
+        .iter()
+        .map(format_todo)
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+pub fn format_todo(todo: &Todo) -> String {
+    format!(
+        "- [{}] {} (Priority: {}, Due: {})",
+        if todo.status == "Done" { "x" } else { " " },
+        todo.text,
+        todo.priority,
+        todo.due
+    )
+}
+
+// Format an open todo with everything needed to rank it
+pub fn format_open_todo(todo: &Todo) -> String {
+    let today = chrono::Local::now().date_naive();
+    let age = chrono::NaiveDate::parse_from_str(&todo.date_added, "%d-%m-%y")
+        .map(|added| format!("{} days", (today - added).num_days()))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!(
+        "- #{} {} (Priority: {}, Due: {}, Status: {}, Owner: {}, Age: {})",
+        todo.id, todo.text, todo.priority, todo.due, todo.status, todo.owner, age
+    )
+}
+
+// PARSE A JSON ANSWER, TOLERATING MARKDOWN CODE FENCES AROUND IT
+pub fn parse_json<T: DeserializeOwned>(text: &str) -> Result<T, Box<dyn Error>> {
+    let start = text
+        .find(['{', '['])
+        .ok_or("The model did not answer with JSON")?;
+    let end = text
+        .rfind(['}', ']'])
+        .ok_or("The model did not answer with JSON")?;
+    if end < start {
+        return Err("The model did not answer with JSON".into());
+    }
+
+    serde_json::from_str(&text[start..=end])
+        .map_err(|e| format!("The model answered with invalid JSON: {}", e).into())
+}
+
+// ANSWER A ONE-OFF PROMPT, STREAMING THE ANSWER THROUGH `on_token`
+pub async fn ask(
+    prompt: String,
+    show_request: bool,
+    on_token: &mut OnToken<'_>,
+) -> Result<String, Box<dyn Error>> {
+    // Get database connection
+    let db =
+        database::DBtodo::new().map_err(|e| format!("Failed to connect to database: {}", e))?;
+
+    // Get todos for context, only what the privacy settings allow
+    let todos = db
+        .get_todos()
+        .map_err(|e| format!("Failed to get todos: {}", e))?;
+    let todos = Privacy::load()?.todos(&todos);
+    let context = TodoContext::load()?.todos(&todos, &prompt);
+
+    let request = CompletionRequest {
+        messages: vec![
+            Message::system(assistant_prompt(&context)),
+            Message::user(format!("USER REQUEST: {}", prompt)),
+        ],
+        ..Default::default()
+    };
+
+    let response = client(show_request)?.stream(&request, on_token).await?;
+    Ok(response.text)
+}
+
+// Create system prompt with instructions and the todos picked by `TodoContext`
+pub fn assistant_prompt(context: &str) -> String {
+    format!(
+        "ROLE: You are an AI assistant for a todo application.
+RULES:
+- Respond in clear markdown formatting
+- Be concise and actionable
+- Reference existing todos when relevant
+- Today is {}
+- Current hour: {}
+
+CURRENT TODOS:
+{}",
+        chrono::Local::now().format("%A, %B %d"),
+        // To the hour, so the prompt and its cached answer stay the same for a while
+        chrono::Local::now().format("%H:00"),
+        context,
+    )
+}
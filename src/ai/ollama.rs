@@ -0,0 +1,350 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+use super::error::{AiError, send};
+use super::openai::{WireTool, role_name, wire_tools};
+use super::provider::{
+    CompletionRequest, CompletionResponse, LlmProvider, OnToken, ToolCall, Usage, format_preview,
+    read_lines,
+};
+
+const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+const DEFAULT_MODEL: &str = "llama3.2";
+const DEFAULT_EMBEDDING_MODEL: &str = "nomic-embed-text";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ChatMessage {
+    role: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<WireToolCall>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct WireToolCall {
+    function: WireFunctionCall,
+}
+
+// Unlike OpenAI, Ollama sends the arguments as a JSON object
+#[derive(Serialize, Deserialize, Debug)]
+struct WireFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct RequestBody {
+    model: String,
+    messages: Vec<ChatMessage>,
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    format: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<WireTool>,
+}
+
+#[derive(Serialize)]
+struct EmbedBody<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize, Debug)]
+struct EmbedResponse {
+    #[serde(default)]
+    embeddings: Vec<Vec<f32>>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChatResponse {
+    message: ChatMessage,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+}
+
+// Ollama reports token counts once the answer is done
+fn usage(prompt_eval_count: Option<u64>, eval_count: Option<u64>) -> Option<Usage> {
+    if prompt_eval_count.is_none() && eval_count.is_none() {
+        return None;
+    }
+    Some(Usage {
+        prompt_tokens: prompt_eval_count.unwrap_or_default(),
+        completion_tokens: eval_count.unwrap_or_default(),
+    })
+}
+
+// One line of a streamed answer
+#[derive(Deserialize, Debug)]
+struct StreamChunk {
+    #[serde(default)]
+    message: Option<ChatMessage>,
+    #[serde(default)]
+    error: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    eval_count: Option<u64>,
+}
+
+/// A local Ollama server
+pub struct Ollama {
+    client: Client,
+    base_url: String,
+    model: String,
+    embedding_model: String,
+}
+
+impl Ollama {
+    pub fn new(
+        client: Client,
+        base_url: Option<String>,
+        model: Option<String>,
+        embedding_model: Option<String>,
+    ) -> Self {
+        Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            embedding_model: embedding_model.unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.to_string()),
+        }
+    }
+
+    fn body(&self, request: &CompletionRequest) -> RequestBody {
+        RequestBody {
+            model: self.model.clone(),
+            messages: request
+                .messages
+                .iter()
+                .map(|m| ChatMessage {
+                    role: role_name(m.role).to_string(),
+                    content: m.content.clone(),
+                    tool_calls: m
+                        .tool_calls
+                        .iter()
+                        .map(|call| WireToolCall {
+                            function: WireFunctionCall {
+                                name: call.name.clone(),
+                                arguments: call.arguments.clone(),
+                            },
+                        })
+                        .collect(),
+                })
+                .collect(),
+            stream: false,
+            format: request.json.then(|| "json".to_string()),
+            tools: wire_tools(&request.tools),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Ollama {
+    async fn complete(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let url = format!("{}/api/chat", self.base_url);
+
+        let response = send(self.client.post(&url).json(&self.body(request)))
+            .await?
+            .json::<ChatResponse>()
+            .await
+            .map_err(AiError::from)?;
+
+        // Ollama doesn't give calls an id, number them instead
+        let tool_calls = response
+            .message
+            .tool_calls
+            .into_iter()
+            .enumerate()
+            .map(|(i, call)| ToolCall {
+                id: format!("call_{}", i),
+                name: call.function.name,
+                arguments: call.function.arguments,
+            })
+            .collect();
+
+        Ok(CompletionResponse {
+            text: response.message.content,
+            tool_calls,
+            usage: usage(response.prompt_eval_count, response.eval_count),
+        })
+    }
+
+    async fn stream(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut OnToken<'_>,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let url = format!("{}/api/chat", self.base_url);
+        let mut body = self.body(request);
+        body.stream = true;
+
+        let response = send(self.client.post(&url).json(&body)).await?;
+
+        // Newline delimited JSON, one chunk per line
+        let mut text = String::new();
+        let mut reported = None;
+        let mut chunks = 0;
+        read_lines(response, |line| {
+            let chunk: StreamChunk = serde_json::from_str(line).map_err(AiError::from)?;
+            chunks += 1;
+            if let Some(error) = chunk.error {
+                return Err(AiError::Provider(error).into());
+            }
+            if let Some(counted) = usage(chunk.prompt_eval_count, chunk.eval_count) {
+                reported = Some(counted);
+            }
+            if let Some(message) = chunk.message {
+                on_token(&message.content);
+                text.push_str(&message.content);
+            }
+            Ok(())
+        })
+        .await?;
+        if chunks == 0 {
+            return Err(AiError::Malformed("the answer stream was empty".to_string()).into());
+        }
+
+        Ok(CompletionResponse {
+            text,
+            usage: reported,
+            ..Default::default()
+        })
+    }
+
+    fn preview(&self, request: &CompletionRequest, stream: bool) -> Result<String, Box<dyn Error>> {
+        let mut body = self.body(request);
+        body.stream = stream;
+        format_preview(&format!("{}/api/chat", self.base_url), &body)
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
+        let url = format!("{}/api/embed", self.base_url);
+        let body = EmbedBody {
+            model: &self.embedding_model,
+            input: texts,
+        };
+        let response = send(self.client.post(&url).json(&body))
+            .await?
+            .json::<EmbedResponse>()
+            .await
+            .map_err(AiError::from)?;
+
+        if response.embeddings.len() != texts.len() {
+            return Err(AiError::Malformed(format!(
+                "expected {} embeddings, got {}",
+                texts.len(),
+                response.embeddings.len()
+            ))
+            .into());
+        }
+        Ok(response.embeddings)
+    }
+
+    fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock::{self, MockServer};
+    use crate::ai::provider::Message;
+
+    fn ollama(url: &str) -> Ollama {
+        Ollama::new(
+            mock::client(),
+            Some(url.to_string()),
+            Some("test-model".to_string()),
+            None,
+        )
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            messages: vec![Message::system("Be brief"), Message::user("Hello")],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn a_reply_is_read_from_the_message() {
+        let server = MockServer::start(vec![mock::json(
+            200,
+            r#"{"message":{"role":"assistant","content":"Hi there"},"done":true}"#,
+        )])
+        .await;
+        let response = ollama(&server.url).complete(&request()).await.unwrap();
+        assert_eq!(response.text, "Hi there");
+
+        let request = &server.requests().await[0];
+        assert!(request.starts_with("POST /api/chat "));
+        assert!(request.contains(r#""model":"test-model""#));
+        assert!(request.contains(r#""stream":false"#));
+    }
+
+    #[tokio::test]
+    async fn a_streamed_reply_is_shown_as_it_arrives() {
+        let server = MockServer::start(vec![mock::stream(
+            "application/x-ndjson",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hi \"},\"done\":false}\n\
+             {\"message\":{\"role\":\"assistant\",\"content\":\"there\"},\"done\":false}\n\
+             {\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true}\n",
+        )])
+        .await;
+        let mut tokens = Vec::new();
+        let mut on_token = |token: &str| tokens.push(token.to_string());
+        let response = ollama(&server.url)
+            .stream(&request(), &mut on_token)
+            .await
+            .unwrap();
+        assert_eq!(response.text, "Hi there");
+        assert_eq!(tokens, ["Hi ", "there", ""]);
+        assert!(server.requests().await[0].contains(r#""stream":true"#));
+    }
+
+    // Stream `body` from a mock server, returning the answer and the tokens shown on the way
+    async fn streamed(body: &str) -> (Result<CompletionResponse, Box<dyn Error>>, Vec<String>) {
+        let server = MockServer::start(vec![mock::stream("application/x-ndjson", body)]).await;
+        let mut tokens = Vec::new();
+        let mut on_token = |token: &str| tokens.push(token.to_string());
+        let response = ollama(&server.url).stream(&request(), &mut on_token).await;
+        (response, tokens)
+    }
+
+    #[tokio::test]
+    async fn an_error_chunk_stops_the_answer() {
+        let (response, tokens) = streamed(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"}}\n\
+             {\"error\":\"model ran out of memory\"}\n\
+             {\"message\":{\"role\":\"assistant\",\"content\":\"lo\"}}\n",
+        )
+        .await;
+        assert!(matches!(
+            response.unwrap_err().downcast_ref::<AiError>(),
+            Some(AiError::Provider(message)) if message == "model ran out of memory"
+        ));
+        assert_eq!(tokens, ["Hel"]);
+    }
+
+    #[tokio::test]
+    async fn an_empty_stream_is_malformed() {
+        let (response, _) = streamed("").await;
+        assert!(matches!(
+            response.unwrap_err().downcast_ref::<AiError>(),
+            Some(AiError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,505 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+
+use super::error::{AiError, send};
+use super::provider::{
+    CompletionRequest, CompletionResponse, LlmProvider, OnToken, Role, Tool, ToolCall, Usage,
+    format_preview, read_lines,
+};
+
+const DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_MODEL: &str = "gpt-4o-mini";
+const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ChatMessage {
+    role: String,
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<WireToolCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct WireToolCall {
+    #[serde(default)]
+    id: String,
+    #[serde(rename = "type", default = "function_type")]
+    kind: String,
+    function: WireFunctionCall,
+}
+
+// Arguments travel as a JSON-encoded string
+#[derive(Serialize, Deserialize, Debug)]
+struct WireFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: String,
+}
+
+fn function_type() -> String {
+    "function".to_string()
+}
+
+#[derive(Serialize)]
+pub struct WireTool {
+    #[serde(rename = "type")]
+    kind: String,
+    function: WireFunction,
+}
+
+#[derive(Serialize)]
+struct WireFunction {
+    name: String,
+    description: String,
+    parameters: serde_json::Value,
+}
+
+#[derive(Serialize)]
+struct ResponseFormat {
+    #[serde(rename = "type")]
+    kind: String,
+}
+
+#[derive(Serialize)]
+struct RequestBody {
+    model: String,
+    messages: Vec<ChatMessage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<ResponseFormat>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<WireTool>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+// Without it streamed answers carry no token counts
+#[derive(Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct WireUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+}
+
+impl From<WireUsage> for Usage {
+    fn from(usage: WireUsage) -> Self {
+        Usage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct Choice {
+    message: ChatMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct Delta {
+    #[serde(default)]
+    content: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamChoice {
+    #[serde(default)]
+    delta: Delta,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+// The answer was withheld by the server's moderation
+fn check_finish(reason: Option<&str>) -> Result<(), AiError> {
+    match reason {
+        Some("content_filter") => Err(AiError::SafetyBlock("content_filter".to_string())),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct StreamChunk {
+    #[serde(default)]
+    choices: Vec<StreamChoice>,
+    // Only on the last chunk
+    #[serde(default)]
+    usage: Option<WireUsage>,
+}
+
+#[derive(Serialize)]
+struct EmbeddingBody<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize, Debug)]
+struct Embedding {
+    #[serde(default)]
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Deserialize, Debug)]
+struct EmbeddingResponse {
+    #[serde(default)]
+    data: Vec<Embedding>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ChatResponse {
+    #[serde(default)]
+    choices: Vec<Choice>,
+    #[serde(default)]
+    usage: Option<WireUsage>,
+}
+
+/// Any server speaking the OpenAI chat-completions API (OpenAI, vLLM, llama.cpp, LM Studio...)
+pub struct OpenAi {
+    client: Client,
+    base_url: String,
+    model: String,
+    embedding_model: String,
+    api_key: Option<String>,
+}
+
+impl OpenAi {
+    pub fn new(
+        client: Client,
+        base_url: Option<String>,
+        model: Option<String>,
+        embedding_model: Option<String>,
+        api_key: Option<String>,
+    ) -> Self {
+        Self {
+            client,
+            base_url: base_url.unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
+            model: model.unwrap_or_else(|| DEFAULT_MODEL.to_string()),
+            embedding_model: embedding_model.unwrap_or_else(|| DEFAULT_EMBEDDING_MODEL.to_string()),
+            api_key,
+        }
+    }
+
+    fn body(&self, request: &CompletionRequest) -> RequestBody {
+        RequestBody {
+            model: self.model.clone(),
+            messages: request
+                .messages
+                .iter()
+                .map(|m| ChatMessage {
+                    role: role_name(m.role).to_string(),
+                    content: Some(m.content.clone()),
+                    tool_calls: m
+                        .tool_calls
+                        .iter()
+                        .map(|call| WireToolCall {
+                            id: call.id.clone(),
+                            kind: function_type(),
+                            function: WireFunctionCall {
+                                name: call.name.clone(),
+                                arguments: call.arguments.to_string(),
+                            },
+                        })
+                        .collect(),
+                    tool_call_id: m.tool_call.as_ref().map(|call| call.id.clone()),
+                })
+                .collect(),
+            response_format: request.json.then(|| ResponseFormat {
+                kind: "json_object".to_string(),
+            }),
+            tools: wire_tools(&request.tools),
+            stream: false,
+            stream_options: None,
+        }
+    }
+
+    fn url(&self) -> String {
+        format!("{}/chat/completions", self.base_url)
+    }
+
+    fn post(&self, body: &RequestBody) -> reqwest::RequestBuilder {
+        self.authorize(self.client.post(self.url()).json(body))
+    }
+
+    fn authorize(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => builder.bearer_auth(key),
+            None => builder,
+        }
+    }
+}
+
+pub fn role_name(role: Role) -> &'static str {
+    match role {
+        Role::System => "system",
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::Tool => "tool",
+    }
+}
+
+// Ollama uses the same tool declarations
+pub fn wire_tools(tools: &[Tool]) -> Vec<WireTool> {
+    tools
+        .iter()
+        .map(|tool| WireTool {
+            kind: function_type(),
+            function: WireFunction {
+                name: tool.name.to_string(),
+                description: tool.description.to_string(),
+                parameters: tool.parameters.clone(),
+            },
+        })
+        .collect()
+}
+
+#[async_trait]
+impl LlmProvider for OpenAi {
+    async fn complete(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let response = send(self.post(&self.body(request)))
+            .await?
+            .json::<ChatResponse>()
+            .await
+            .map_err(AiError::from)?;
+
+        let usage = response.usage.map(Usage::from);
+        let choice = response
+            .choices
+            .into_iter()
+            .next()
+            .ok_or_else(|| AiError::Malformed("the answer has no choices".to_string()))?;
+        check_finish(choice.finish_reason.as_deref())?;
+        let message = choice.message;
+
+        let mut tool_calls = Vec::new();
+        for call in message.tool_calls {
+            let arguments = if call.function.arguments.trim().is_empty() {
+                serde_json::json!({})
+            } else {
+                serde_json::from_str(&call.function.arguments).map_err(AiError::from)?
+            };
+            tool_calls.push(ToolCall {
+                id: call.id,
+                name: call.function.name,
+                arguments,
+            });
+        }
+
+        Ok(CompletionResponse {
+            text: message.content.unwrap_or_default(),
+            tool_calls,
+            usage,
+        })
+    }
+
+    async fn stream(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut OnToken<'_>,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let mut body = self.body(request);
+        body.stream = true;
+        body.stream_options = Some(StreamOptions {
+            include_usage: true,
+        });
+
+        let response = send(self.post(&body)).await?;
+
+        // SSE events with a `delta`, closed by `data: [DONE]`
+        let mut text = String::new();
+        let mut usage = None;
+        let mut events = 0;
+        read_lines(response, |line| {
+            let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                return Ok(());
+            };
+            events += 1;
+            if data == "[DONE]" {
+                return Ok(());
+            }
+            let chunk: StreamChunk = serde_json::from_str(data).map_err(AiError::from)?;
+            if let Some(reported) = chunk.usage {
+                usage = Some(Usage::from(reported));
+            }
+            if let Some(choice) = chunk.choices.into_iter().next() {
+                check_finish(choice.finish_reason.as_deref())?;
+                if let Some(token) = choice.delta.content {
+                    on_token(&token);
+                    text.push_str(&token);
+                }
+            }
+            Ok(())
+        })
+        .await?;
+        if events == 0 {
+            return Err(AiError::Malformed("the answer stream was empty".to_string()).into());
+        }
+
+        Ok(CompletionResponse {
+            text,
+            usage,
+            ..Default::default()
+        })
+    }
+
+    fn preview(&self, request: &CompletionRequest, stream: bool) -> Result<String, Box<dyn Error>> {
+        let mut body = self.body(request);
+        body.stream = stream;
+        format_preview(&self.url(), &body)
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
+        let url = format!("{}/embeddings", self.base_url);
+        let body = EmbeddingBody {
+            model: &self.embedding_model,
+            input: texts,
+        };
+        let mut response = send(self.authorize(self.client.post(&url).json(&body)))
+            .await?
+            .json::<EmbeddingResponse>()
+            .await
+            .map_err(AiError::from)?;
+
+        if response.data.len() != texts.len() {
+            return Err(AiError::Malformed(format!(
+                "expected {} embeddings, got {}",
+                texts.len(),
+                response.data.len()
+            ))
+            .into());
+        }
+        response.data.sort_by_key(|embedding| embedding.index);
+        Ok(response
+            .data
+            .into_iter()
+            .map(|embedding| embedding.embedding)
+            .collect())
+    }
+
+    fn embedding_model(&self) -> &str {
+        &self.embedding_model
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock::{self, MockServer};
+    use crate::ai::provider::Message;
+
+    fn openai(url: &str) -> OpenAi {
+        OpenAi::new(
+            mock::client(),
+            Some(url.to_string()),
+            Some("test-model".to_string()),
+            None,
+            Some("secret".to_string()),
+        )
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            messages: vec![Message::system("Be brief"), Message::user("Hello")],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn a_reply_is_read_from_the_first_choice() {
+        let server = MockServer::start(vec![mock::json(
+            200,
+            r#"{"choices":[{"message":{"role":"assistant","content":"Hi there"}}]}"#,
+        )])
+        .await;
+        let response = openai(&server.url).complete(&request()).await.unwrap();
+        assert_eq!(response.text, "Hi there");
+
+        let request = &server.requests().await[0];
+        assert!(request.starts_with("POST /chat/completions "));
+        assert!(request.contains("authorization: Bearer secret"));
+        assert!(request.contains(r#""model":"test-model""#));
+    }
+
+    #[tokio::test]
+    async fn a_streamed_reply_is_shown_as_it_arrives() {
+        let server = MockServer::start(vec![mock::stream(
+            "text/event-stream",
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{\"content\":\"Hi \"}}]}\n\n\
+             : keep-alive\n\n\
+             data:{\"choices\":[{\"delta\":{\"content\":\"there\"}}]}\n\n\
+             data: [DONE]\n\n",
+        )])
+        .await;
+        let mut tokens = Vec::new();
+        let mut on_token = |token: &str| tokens.push(token.to_string());
+        let response = openai(&server.url)
+            .stream(&request(), &mut on_token)
+            .await
+            .unwrap();
+        assert_eq!(response.text, "Hi there");
+        assert_eq!(tokens, ["Hi ", "there"]);
+        assert!(server.requests().await[0].contains(r#""stream":true"#));
+    }
+
+    // Stream `body` from a mock server, returning the answer and the tokens shown on the way
+    async fn streamed(body: &str) -> (Result<CompletionResponse, Box<dyn Error>>, Vec<String>) {
+        let server = MockServer::start(vec![mock::stream("text/event-stream", body)]).await;
+        let mut tokens = Vec::new();
+        let mut on_token = |token: &str| tokens.push(token.to_string());
+        let response = openai(&server.url).stream(&request(), &mut on_token).await;
+        (response, tokens)
+    }
+
+    #[tokio::test]
+    async fn a_filtered_answer_is_a_safety_block() {
+        let (response, tokens) = streamed(
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hi\"}}]}\n\n\
+             data: {\"choices\":[{\"delta\":{},\"finish_reason\":\"content_filter\"}]}\n\n",
+        )
+        .await;
+        assert!(matches!(
+            response.unwrap_err().downcast_ref::<AiError>(),
+            Some(AiError::SafetyBlock(_))
+        ));
+        assert_eq!(tokens, ["Hi"]);
+    }
+
+    #[tokio::test]
+    async fn a_stream_without_events_is_malformed() {
+        let (response, _) = streamed(": keep-alive\n\nevent: ping\n\n").await;
+        assert!(matches!(
+            response.unwrap_err().downcast_ref::<AiError>(),
+            Some(AiError::Malformed(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn invalid_json_is_malformed() {
+        let (response, _) = streamed("data: {\"choices\":\n\n").await;
+        assert!(matches!(
+            response.unwrap_err().downcast_ref::<AiError>(),
+            Some(AiError::Malformed(_))
+        ));
+    }
+}
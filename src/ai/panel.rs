@@ -0,0 +1,314 @@
+use std::error::Error;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+use super::context::TodoContext;
+use super::privacy::Privacy;
+use super::provider::{CompletionRequest, LlmProvider, Message};
+use crate::arguments::{add_todo::build_todo, models::Todo};
+use crate::database::DBtodo;
+use crate::events::{AppEvent, EventSender};
+
+// Lines the model starts with this marker become todos that can be added with one key
+const TODO_MARKER: &str = "TODO:";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Focus {
+    // Single keys run quick actions, scroll and apply suggestions
+    Actions,
+    // Typing a prompt
+    Input,
+}
+
+#[derive(Debug, Clone)]
+pub enum Suggestion {
+    NewTodo(String),
+    Description { id: usize, text: String },
+}
+
+impl Suggestion {
+    pub fn label(&self) -> String {
+        match self {
+            Suggestion::NewTodo(text) => format!("Add todo \"{}\"", text),
+            Suggestion::Description { id, .. } => format!("Use as description of #{}", id),
+        }
+    }
+}
+
+/// What the streaming task sends back to the TUI
+#[derive(Debug)]
+pub enum PanelEvent {
+    Token(String),
+    Done,
+    Failed(String),
+}
+
+// What the answer is for, decides which suggestions it offers
+#[derive(Debug, Clone, Copy)]
+enum Purpose {
+    Chat,
+    Rewrite(usize),
+}
+
+/// State of the AI side panel in the TUI
+#[derive(Debug)]
+pub struct AiPanel {
+    pub focus: Focus,
+    pub input: String,
+    pub title: String,
+    pub response: String,
+    pub scroll: u16,
+    pub error: Option<String>,
+    pub notice: Option<String>,
+    pub suggestions: Vec<Suggestion>,
+    purpose: Purpose,
+    events: EventSender,
+    // Resolved before the TUI took over the terminal, the key command may need to prompt
+    client: Result<Arc<dyn LlmProvider>, String>,
+    // Numbers the answers, so tokens still queued from a cancelled one are dropped
+    request: u64,
+    task: Option<JoinHandle<()>>,
+}
+
+impl AiPanel {
+    pub fn new(events: EventSender, client: Result<Arc<dyn LlmProvider>, String>) -> Self {
+        Self {
+            focus: Focus::Actions,
+            input: String::new(),
+            title: String::new(),
+            response: String::new(),
+            scroll: 0,
+            error: None,
+            notice: None,
+            suggestions: Vec::new(),
+            purpose: Purpose::Chat,
+            events,
+            client,
+            request: 0,
+            task: None,
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.task.is_some()
+    }
+
+    // Send what was typed in the prompt box
+    pub fn submit(&mut self, todos: &[Todo]) {
+        let prompt = self.input.trim().to_string();
+        if prompt.is_empty() {
+            return;
+        }
+        self.input.clear();
+        self.focus = Focus::Actions;
+        self.start(
+            prompt.clone(),
+            Purpose::Chat,
+            todos,
+            format!("USER REQUEST: {}", prompt),
+        );
+    }
+
+    pub fn summarise(&mut self, todos: &[Todo], todo: &Todo) {
+        let Some(todo) = self.shareable(todo) else {
+            return;
+        };
+        self.start(
+            format!("Summarise #{}", todo.id),
+            Purpose::Chat,
+            todos,
+            format!(
+                "Summarise this todo in a few bullet points: what it is, where it stands and what to do next.\n\n{}",
+                describe(&todo)
+            ),
+        );
+    }
+
+    pub fn suggest_next(&mut self, todos: &[Todo]) {
+        self.start(
+            "Suggest next task".to_string(),
+            Purpose::Chat,
+            todos,
+            "Which open todo should I work on next, and why? Keep it short. If something important is missing from the list, suggest it as a new todo.".to_string(),
+        );
+    }
+
+    pub fn rewrite_description(&mut self, todos: &[Todo], todo: &Todo) {
+        let Some(todo) = self.shareable(todo) else {
+            return;
+        };
+        self.start(
+            format!("Rewrite description of #{}", todo.id),
+            Purpose::Rewrite(todo.id),
+            todos,
+            format!(
+                "Rewrite the description of this todo so it is clear and actionable. Answer with the new description only, in plain text, no heading and no TODO: lines.\n\n{}",
+                describe(&todo)
+            ),
+        );
+    }
+
+    // The todo as the privacy settings let the model see it
+    fn shareable(&mut self, todo: &Todo) -> Option<Todo> {
+        match Privacy::load().map(|privacy| privacy.todo(todo)) {
+            Ok(Some(todo)) => Some(todo),
+            Ok(None) => {
+                self.error = Some(
+                    "This todo's topic is excluded from AI in the [PRIVACY] settings".to_string(),
+                );
+                None
+            }
+            Err(e) => {
+                self.error = Some(e.to_string());
+                None
+            }
+        }
+    }
+
+    // Stream an answer on a background task, its tokens come back through `handle`
+    fn start(&mut self, title: String, purpose: Purpose, todos: &[Todo], request: String) {
+        self.cancel();
+
+        let loaded = self
+            .client
+            .clone()
+            .map_err(Box::<dyn Error>::from)
+            .and_then(|client| Ok((client, Privacy::load()?, TodoContext::load()?)));
+        let (client, privacy, context) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                self.error = Some(e.to_string());
+                return;
+            }
+        };
+
+        let request = CompletionRequest {
+            messages: vec![
+                Message::system(format!(
+                    "{}\n\nWhen you suggest new todos, put each one on its own line starting with \"{} \"",
+                    super::assistant_prompt(&context.todos(&privacy.todos(todos), &request)),
+                    TODO_MARKER
+                )),
+                Message::user(request),
+            ],
+            ..Default::default()
+        };
+
+        self.title = title;
+        self.purpose = purpose;
+        self.response.clear();
+        self.scroll = 0;
+        self.error = None;
+        self.notice = None;
+        self.suggestions.clear();
+
+        let sender = self.events.clone();
+        let number = self.request;
+        self.task = Some(tokio::spawn(async move {
+            let tokens = sender.clone();
+            let mut on_token = move |token: &str| {
+                let _ = tokens.send(AppEvent::Ai(number, PanelEvent::Token(token.to_string())));
+            };
+            let event = match client.stream(&request, &mut on_token).await {
+                Ok(_) => PanelEvent::Done,
+                Err(e) => PanelEvent::Failed(e.to_string()),
+            };
+            let _ = sender.send(AppEvent::Ai(number, event));
+        }));
+    }
+
+    // Something the streaming task sent
+    pub fn handle(&mut self, request: u64, event: PanelEvent) {
+        if request != self.request || self.task.is_none() {
+            return;
+        }
+
+        match event {
+            PanelEvent::Token(token) => {
+                self.response.push_str(&token);
+                return;
+            }
+            PanelEvent::Done => {}
+            PanelEvent::Failed(e) => self.error = Some(e),
+        }
+        self.task = None;
+        self.suggestions = self.parse_suggestions();
+    }
+
+    pub fn cancel(&mut self) {
+        self.request += 1;
+        if let Some(task) = self.task.take() {
+            task.abort();
+            self.notice = Some("Cancelled".to_string());
+        }
+    }
+
+    fn parse_suggestions(&self) -> Vec<Suggestion> {
+        if self.error.is_some() {
+            return Vec::new();
+        }
+        match self.purpose {
+            Purpose::Rewrite(id) if !self.response.trim().is_empty() => {
+                vec![Suggestion::Description {
+                    id,
+                    text: self.response.trim().to_string(),
+                }]
+            }
+            Purpose::Rewrite(_) => Vec::new(),
+            Purpose::Chat => self
+                .response
+                .lines()
+                .filter_map(suggested_todo)
+                .map(Suggestion::NewTodo)
+                .collect(),
+        }
+    }
+
+    // Add the numbered suggestion to the database, returns what was done
+    pub fn apply(&mut self, number: usize) -> Result<String, Box<dyn Error>> {
+        let suggestion = self
+            .suggestions
+            .get(number.wrapping_sub(1))
+            .ok_or(format!("No suggestion {}", number))?
+            .clone();
+
+        let db = DBtodo::new()?;
+        let message = match &suggestion {
+            Suggestion::NewTodo(text) => {
+                let todo = build_todo(text.clone(), None, None, None, None, None)?;
+                let id = db.add_todo(&todo)?;
+                format!("Added todo #{}", id)
+            }
+            Suggestion::Description { id, text } => {
+                let mut todo = db
+                    .get_todo(*id as i32)?
+                    .ok_or(format!("No todo found with id: {}", id))?;
+                todo.desc = text.clone();
+                db.edit_todo(&todo)?;
+                format!("Updated the description of #{}", id)
+            }
+        };
+
+        self.suggestions.remove(number - 1);
+        self.notice = Some(message.clone());
+        Ok(message)
+    }
+}
+
+// "TODO: Book flights" / "- **TODO:** Book flights" -> "Book flights"
+pub fn suggested_todo(line: &str) -> Option<String> {
+    let line = line
+        .trim()
+        .trim_start_matches(['-', '*', '+', ' '])
+        .trim_start_matches("**");
+    let rest = line.strip_prefix(TODO_MARKER)?;
+    let text = rest.trim_start_matches("**").trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+fn describe(todo: &Todo) -> String {
+    format!(
+        "TODO #{}: {}\nSTATUS: {}\nPRIORITY: {}\nTOPIC: {}\nOWNER: {}\nDUE: {}\nDESCRIPTION: {}",
+        todo.id, todo.text, todo.status, todo.priority, todo.topic, todo.owner, todo.due, todo.desc
+    )
+}
@@ -0,0 +1,319 @@
+use async_trait::async_trait;
+use regex::Regex;
+use std::error::Error;
+use std::fmt;
+
+use super::provider::{CompletionRequest, CompletionResponse, LlmProvider, OnToken};
+use crate::arguments::models::{StatusChange, Todo};
+use crate::configs::{AppConfigs, TODO_FIELDS};
+
+const HIDDEN: &str = "[hidden]";
+const REDACTED: &str = "[redacted]";
+
+/// Returned instead of an answer when the request was only printed
+#[derive(Debug)]
+pub struct RequestShown;
+
+impl fmt::Display for RequestShown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The request above was not sent")
+    }
+}
+
+impl Error for RequestShown {}
+
+/// What todo data may be sent to the AI provider, from the `[PRIVACY]` config
+pub struct Privacy {
+    fields: Vec<String>,
+    exclude_topics: Vec<String>,
+    redact: Vec<Regex>,
+    max_todos: Option<usize>,
+}
+
+impl Privacy {
+    pub fn load() -> Result<Self, Box<dyn Error>> {
+        Self::from_config(&AppConfigs::new()?)
+    }
+
+    pub fn from_config(configs: &AppConfigs) -> Result<Self, Box<dyn Error>> {
+        let privacy = &configs.privacy;
+
+        let mut fields = Vec::new();
+        for field in &privacy.fields {
+            let field = field.trim().to_lowercase();
+            if !TODO_FIELDS.contains(&field.as_str()) {
+                return Err(format!(
+                    "Unknown field '{}' in [PRIVACY] fields, expected some of {}",
+                    field,
+                    TODO_FIELDS.join(", ")
+                )
+                .into());
+            }
+            fields.push(field);
+        }
+
+        let redact = privacy
+            .redact
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern)
+                    .map_err(|e| format!("Invalid [PRIVACY] redact pattern '{}': {}", pattern, e))
+            })
+            .collect::<Result<Vec<Regex>, String>>()?;
+
+        Ok(Self {
+            fields,
+            exclude_topics: privacy
+                .exclude_topics
+                .iter()
+                .map(|topic| topic.trim().to_lowercase())
+                .collect(),
+            redact,
+            max_todos: privacy.max_todos,
+        })
+    }
+
+    /// Whether `field` is one of the `[PRIVACY] fields`
+    pub fn sends(&self, field: &str) -> bool {
+        self.fields.iter().any(|f| f == field)
+    }
+
+    fn excluded(&self, todo: &Todo) -> bool {
+        self.exclude_topics
+            .contains(&todo.topic.trim().to_lowercase())
+    }
+
+    /// The todo as it may be shown to the model, None when its topic is excluded
+    pub fn todo(&self, todo: &Todo) -> Option<Todo> {
+        if self.excluded(todo) {
+            return None;
+        }
+
+        let hide = |field: &str, value: &String| {
+            if self.sends(field) {
+                value.clone()
+            } else {
+                HIDDEN.to_string()
+            }
+        };
+        Some(Todo {
+            text: hide("text", &todo.text),
+            desc: hide("desc", &todo.desc),
+            topic: hide("topic", &todo.topic),
+            priority: hide("priority", &todo.priority),
+            status: hide("status", &todo.status),
+            owner: hide("owner", &todo.owner),
+            due: hide("due", &todo.due),
+            date_added: hide("created", &todo.date_added),
+            ..todo.clone()
+        })
+    }
+
+    /// The todos that may be shown to the model, open ones first when `max_todos` cuts the list.
+    /// Without the status field done todos are left out, they'd read as open once it's hidden.
+    pub fn todos(&self, todos: &[Todo]) -> Vec<Todo> {
+        let mut shared = todos
+            .iter()
+            .filter(|todo| !self.excluded(todo))
+            .filter(|todo| self.sends("status") || todo.status != "Done")
+            .collect::<Vec<&Todo>>();
+
+        if let Some(max) = self.max_todos
+            && shared.len() > max
+        {
+            shared.sort_by_key(|todo| todo.status == "Done");
+            shared.truncate(max);
+            shared.sort_by_key(|todo| todo.id);
+        }
+        shared
+            .into_iter()
+            .filter_map(|todo| self.todo(todo))
+            .collect()
+    }
+
+    /// Status changes of the todos that may be shown, `shared` comes from `todos`
+    pub fn status_changes(&self, changes: &[StatusChange], shared: &[Todo]) -> Vec<StatusChange> {
+        changes
+            .iter()
+            .filter_map(|change| {
+                let todo = shared.iter().find(|todo| todo.id == change.todo_id)?;
+                Some(StatusChange {
+                    text: todo.text.clone(),
+                    ..change.clone()
+                })
+            })
+            .collect()
+    }
+
+    pub fn redact(&self, text: &str) -> String {
+        let mut text = text.to_string();
+        for pattern in &self.redact {
+            text = pattern.replace_all(&text, REDACTED).into_owned();
+        }
+        text
+    }
+
+    fn redact_request(&self, request: &CompletionRequest) -> CompletionRequest {
+        let mut request = request.clone();
+        for message in &mut request.messages {
+            message.content = self.redact(&message.content);
+            for call in message
+                .tool_calls
+                .iter_mut()
+                .chain(message.tool_call.as_mut())
+            {
+                if let Ok(arguments) =
+                    serde_json::from_str(&self.redact(&call.arguments.to_string()))
+                {
+                    call.arguments = arguments;
+                }
+            }
+        }
+        request
+    }
+}
+
+/// Wraps the configured provider so every request is redacted, or only printed on `--show-request`
+pub struct Guarded {
+    inner: Box<dyn LlmProvider>,
+    privacy: Privacy,
+    show_request: bool,
+}
+
+impl Guarded {
+    pub fn new(inner: Box<dyn LlmProvider>, privacy: Privacy, show_request: bool) -> Self {
+        Self {
+            inner,
+            privacy,
+            show_request,
+        }
+    }
+
+    fn check(
+        &self,
+        request: &CompletionRequest,
+        stream: bool,
+    ) -> Result<CompletionRequest, Box<dyn Error>> {
+        let request = self.privacy.redact_request(request);
+        if self.show_request {
+            println!("{}", self.inner.preview(&request, stream)?);
+            return Err(Box::new(RequestShown));
+        }
+        Ok(request)
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Guarded {
+    async fn complete(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let request = self.check(request, false)?;
+        self.inner.complete(&request).await
+    }
+
+    async fn stream(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut OnToken<'_>,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let request = self.check(request, true)?;
+        self.inner.stream(&request, on_token).await
+    }
+
+    fn preview(&self, request: &CompletionRequest, stream: bool) -> Result<String, Box<dyn Error>> {
+        self.inner
+            .preview(&self.privacy.redact_request(request), stream)
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
+        let texts = texts
+            .iter()
+            .map(|text| self.privacy.redact(text))
+            .collect::<Vec<String>>();
+        if self.show_request {
+            println!(
+                "EMBED {}\n{}",
+                self.inner.embedding_model(),
+                serde_json::to_string_pretty(&texts)?
+            );
+            return Err(Box::new(RequestShown));
+        }
+        self.inner.embed(&texts).await
+    }
+
+    fn embedding_model(&self) -> &str {
+        self.inner.embedding_model()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn privacy(fields: &[&str], max_todos: Option<usize>) -> Privacy {
+        Privacy {
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+            exclude_topics: vec!["private".to_string()],
+            redact: Vec::new(),
+            max_todos,
+        }
+    }
+
+    fn todo(id: usize, topic: &str, status: &str) -> Todo {
+        Todo {
+            id,
+            priority: "Normal".to_string(),
+            topic: topic.to_string(),
+            text: format!("Todo {}", id),
+            desc: String::new(),
+            date_added: "01-10-26".to_string(),
+            status: status.to_string(),
+            owner: "Alex".to_string(),
+            due: "-".to_string(),
+            parent_id: None,
+        }
+    }
+
+    fn ids(todos: &[Todo]) -> Vec<usize> {
+        todos.iter().map(|todo| todo.id).collect()
+    }
+
+    #[test]
+    fn excluded_topics_and_hidden_fields_are_left_out() {
+        let todos = [todo(1, "Work", "Pending"), todo(2, "Private", "Pending")];
+        let shared = privacy(&["text", "status"], None).todos(&todos);
+        assert_eq!(ids(&shared), [1]);
+        assert_eq!(shared[0].text, "Todo 1");
+        assert_eq!(shared[0].owner, HIDDEN);
+    }
+
+    #[test]
+    fn done_todos_are_left_out_when_the_status_is_hidden() {
+        let todos = [todo(1, "Work", "Done"), todo(2, "Work", "Ongoing")];
+        assert_eq!(
+            ids(&privacy(&["text", "status"], None).todos(&todos)),
+            [1, 2]
+        );
+
+        let shared = privacy(&["text"], None).todos(&todos);
+        assert_eq!(ids(&shared), [2]);
+        assert_eq!(shared[0].status, HIDDEN);
+    }
+
+    #[test]
+    fn max_todos_keeps_open_todos_first() {
+        let todos = [
+            todo(1, "Work", "Done"),
+            todo(2, "Work", "Pending"),
+            todo(3, "Work", "Done"),
+            todo(4, "Work", "Ongoing"),
+        ];
+        assert_eq!(ids(&privacy(&["status"], Some(3)).todos(&todos)), [1, 2, 4]);
+    }
+}
@@ -0,0 +1,560 @@
+use async_trait::async_trait;
+use std::error::Error;
+use std::time::Duration;
+
+use super::error::AiError;
+use super::{gemini::Gemini, ollama::Ollama, openai::OpenAi};
+use crate::configs::AppConfigs;
+use crate::credentials;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Role {
+    System,
+    #[default]
+    User,
+    Assistant,
+    /// The result of a tool call, sent back to the model
+    Tool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+    /// Tools the assistant asked to run in this turn
+    pub tool_calls: Vec<ToolCall>,
+    /// For `Role::Tool`: the call this message answers
+    pub tool_call: Option<ToolCall>,
+}
+
+impl Message {
+    pub fn system(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::System,
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn user(content: impl Into<String>) -> Self {
+        Self {
+            role: Role::User,
+            content: content.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn assistant(content: impl Into<String>, tool_calls: Vec<ToolCall>) -> Self {
+        Self {
+            role: Role::Assistant,
+            content: content.into(),
+            tool_calls,
+            ..Default::default()
+        }
+    }
+
+    pub fn tool_result(call: &ToolCall, content: impl Into<String>) -> Self {
+        Self {
+            role: Role::Tool,
+            content: content.into(),
+            tool_call: Some(call.clone()),
+            ..Default::default()
+        }
+    }
+}
+
+/// A function the model may call, `parameters` is a JSON schema
+#[derive(Debug, Clone)]
+pub struct Tool {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CompletionRequest {
+    pub messages: Vec<Message>,
+    /// Ask the model to answer with a JSON document only
+    pub json: bool,
+    pub tools: Vec<Tool>,
+}
+
+/// Tokens billed for one call, as reported by the provider
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CompletionResponse {
+    pub text: String,
+    pub tool_calls: Vec<ToolCall>,
+    /// None when the provider didn't report it
+    pub usage: Option<Usage>,
+}
+
+impl std::fmt::Debug for dyn LlmProvider {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LlmProvider({})", self.model())
+    }
+}
+
+// Receives each piece of a streamed answer as it arrives
+pub type OnToken<'a> = dyn FnMut(&str) + Send + 'a;
+
+/// A chat model backend. Each implementation owns its endpoint, wire format and model.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    async fn complete(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, Box<dyn Error>>;
+
+    /// Like `complete`, calling `on_token` as text arrives. Tool calls aren't streamed.
+    /// Providers without a streaming endpoint send the whole answer at once.
+    async fn stream(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut OnToken<'_>,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let response = self.complete(request).await?;
+        on_token(&response.text);
+        Ok(response)
+    }
+
+    /// The HTTP request `complete` (or `stream`) would send, for `--show-request`.
+    /// Credentials are left out.
+    fn preview(&self, request: &CompletionRequest, stream: bool) -> Result<String, Box<dyn Error>>;
+
+    /// The model answering, for usage accounting
+    fn model(&self) -> &str;
+
+    /// One vector per text from the provider's embeddings endpoint
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn Error>>;
+
+    /// The model behind `embed`, vectors of different models can't be compared
+    fn embedding_model(&self) -> &str;
+}
+
+// FORMAT A REQUEST FOR `preview`
+pub fn format_preview(url: &str, body: &impl serde::Serialize) -> Result<String, Box<dyn Error>> {
+    Ok(format!(
+        "POST {}\n{}",
+        url,
+        serde_json::to_string_pretty(body)?
+    ))
+}
+
+// FEED EACH LINE OF A STREAMED HTTP BODY TO `on_line` (SSE AND NDJSON ARE BOTH LINE BASED)
+pub async fn read_lines(
+    mut response: reqwest::Response,
+    mut on_line: impl FnMut(&str) -> Result<(), Box<dyn Error>> + Send,
+) -> Result<(), Box<dyn Error>> {
+    let mut lines = LineBuffer::default();
+    while let Some(chunk) = response.chunk().await.map_err(AiError::from)? {
+        lines.push(&chunk, &mut on_line)?;
+    }
+    lines.finish(&mut on_line)
+}
+
+// Bytes of a streamed body that don't make a complete line yet
+#[derive(Default)]
+struct LineBuffer {
+    bytes: Vec<u8>,
+}
+
+impl LineBuffer {
+    // Only hand over complete lines, a chunk can end mid-character
+    fn push(
+        &mut self,
+        chunk: &[u8],
+        on_line: &mut impl FnMut(&str) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        self.bytes.extend_from_slice(chunk);
+        while let Some(end) = self.bytes.iter().position(|b| *b == b'\n') {
+            let line = self.bytes.drain(..=end).collect::<Vec<u8>>();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim();
+            if !line.is_empty() {
+                on_line(line)?;
+            }
+        }
+        Ok(())
+    }
+
+    // The last line doesn't have to end with a newline
+    fn finish(
+        self,
+        on_line: &mut impl FnMut(&str) -> Result<(), Box<dyn Error>>,
+    ) -> Result<(), Box<dyn Error>> {
+        let rest = String::from_utf8_lossy(&self.bytes);
+        if !rest.trim().is_empty() {
+            on_line(rest.trim())?;
+        }
+        Ok(())
+    }
+}
+
+/// Retries rate limits and server errors with exponential backoff
+pub struct Retrying {
+    inner: Box<dyn LlmProvider>,
+    max_retries: u32,
+}
+
+impl Retrying {
+    pub fn new(inner: Box<dyn LlmProvider>, max_retries: u32) -> Self {
+        Self { inner, max_retries }
+    }
+
+    // How long to wait before the next attempt, None when the error isn't worth retrying
+    fn delay(&self, error: &(dyn Error + 'static), attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+        let error = error
+            .downcast_ref::<AiError>()
+            .filter(|e| e.is_retryable())?;
+        let backoff = Duration::from_millis(500 * 2u64.pow(attempt));
+        Some(error.retry_after().unwrap_or(backoff).min(MAX_RETRY_DELAY))
+    }
+}
+
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+#[async_trait]
+impl LlmProvider for Retrying {
+    async fn complete(
+        &self,
+        request: &CompletionRequest,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let mut attempt = 0;
+        loop {
+            let delay = match self.inner.complete(request).await {
+                Err(e) => match self.delay(e.as_ref(), attempt) {
+                    Some(delay) => delay,
+                    None => return Err(e),
+                },
+                response => return response,
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    async fn stream(
+        &self,
+        request: &CompletionRequest,
+        on_token: &mut OnToken<'_>,
+    ) -> Result<CompletionResponse, Box<dyn Error>> {
+        let mut attempt = 0;
+        loop {
+            // Once tokens were shown a retry would repeat them
+            let mut started = false;
+            let mut track = |token: &str| {
+                started = true;
+                on_token(token);
+            };
+            let delay = match self.inner.stream(request, &mut track).await {
+                Err(e) if !started => match self.delay(e.as_ref(), attempt) {
+                    Some(delay) => delay,
+                    None => return Err(e),
+                },
+                response => return response,
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn preview(&self, request: &CompletionRequest, stream: bool) -> Result<String, Box<dyn Error>> {
+        self.inner.preview(request, stream)
+    }
+
+    fn model(&self) -> &str {
+        self.inner.model()
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, Box<dyn Error>> {
+        let mut attempt = 0;
+        loop {
+            let delay = match self.inner.embed(texts).await {
+                Err(e) => match self.delay(e.as_ref(), attempt) {
+                    Some(delay) => delay,
+                    None => return Err(e),
+                },
+                vectors => return vectors,
+            };
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    fn embedding_model(&self) -> &str {
+        self.inner.embedding_model()
+    }
+}
+
+// BUILD THE PROVIDER SELECTED IN THE CONFIG
+pub fn from_config(
+    configs: &AppConfigs,
+    show_request: bool,
+) -> Result<Box<dyn LlmProvider>, Box<dyn Error>> {
+    // A read timeout rather than a total one, so long streamed answers aren't cut off
+    let client = reqwest::Client::builder()
+        .read_timeout(Duration::from_secs(configs.timeout_secs))
+        .connect_timeout(Duration::from_secs(configs.connect_timeout_secs))
+        .build()?;
+
+    let provider: Box<dyn LlmProvider> = match configs.provider.as_str() {
+        "gemini" => Box::new(Gemini::new(
+            client,
+            configs.base_url.clone(),
+            configs.model.clone(),
+            configs.embedding_model.clone(),
+            // Showing the request doesn't send it, so it doesn't need the key
+            match credentials::get_api_key() {
+                Err(_) if show_request => String::new(),
+                key => key?,
+            },
+        )),
+        // Self-hosted OpenAI-compatible servers often run without a key
+        "openai" => Box::new(OpenAi::new(
+            client,
+            configs.base_url.clone(),
+            configs.model.clone(),
+            configs.embedding_model.clone(),
+            credentials::get_api_key().ok(),
+        )),
+        "ollama" => Box::new(Ollama::new(
+            client,
+            configs.base_url.clone(),
+            configs.model.clone(),
+            configs.embedding_model.clone(),
+        )),
+        other => {
+            return Err(format!(
+                "Unknown AI provider '{}', expected gemini, openai or ollama",
+                other
+            )
+            .into());
+        }
+    };
+
+    Ok(Box::new(Retrying::new(provider, configs.max_retries)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai::mock::{self, MockServer};
+
+    fn retrying(url: &str, max_retries: u32) -> Retrying {
+        let openai = OpenAi::new(mock::client(), Some(url.to_string()), None, None, None);
+        Retrying::new(Box::new(openai), max_retries)
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            messages: vec![Message::user("Hello")],
+            ..Default::default()
+        }
+    }
+
+    fn answer(text: &str) -> String {
+        mock::json(
+            200,
+            &format!(
+                r#"{{"choices":[{{"message":{{"role":"assistant","content":"{}"}}}}]}}"#,
+                text
+            ),
+        )
+    }
+
+    fn ai_error(error: Box<dyn Error>) -> AiError {
+        *error.downcast::<AiError>().unwrap()
+    }
+
+    #[test]
+    fn backoff_doubles_with_each_attempt() {
+        let retrying = retrying("http://127.0.0.1:9", 5);
+        let server = AiError::Server {
+            status: 503,
+            message: String::new(),
+        };
+        let delays = (0..4)
+            .map(|attempt| retrying.delay(&server, attempt))
+            .collect::<Vec<Option<Duration>>>();
+        assert_eq!(
+            delays,
+            [500, 1000, 2000, 4000].map(|ms| Some(Duration::from_millis(ms)))
+        );
+    }
+
+    #[test]
+    fn retry_after_is_capped() {
+        let retrying = retrying("http://127.0.0.1:9", 3);
+        let quota = AiError::Quota {
+            message: String::new(),
+            retry_after: Some(Duration::from_secs(600)),
+        };
+        assert_eq!(retrying.delay(&quota, 0), Some(MAX_RETRY_DELAY));
+        // Out of attempts
+        assert_eq!(retrying.delay(&quota, 3), None);
+    }
+
+    #[tokio::test]
+    async fn a_rate_limit_is_retried_after_retry_after() {
+        let server = MockServer::start(vec![
+            mock::response(429, &[("Retry-After", "0")], "slow down"),
+            answer("ok"),
+        ])
+        .await;
+        let response = retrying(&server.url, 3).complete(&request()).await;
+        assert_eq!(response.unwrap().text, "ok");
+        assert_eq!(server.requests().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_read_from_the_response() {
+        let server =
+            MockServer::start(vec![mock::response(429, &[("Retry-After", "7")], "")]).await;
+        let retrying = retrying(&server.url, 3);
+        // Retrying isn't involved in a single attempt
+        let error = retrying.inner.complete(&request()).await.unwrap_err();
+        assert_eq!(
+            retrying.delay(error.as_ref(), 0),
+            Some(Duration::from_secs(7))
+        );
+    }
+
+    #[tokio::test]
+    async fn a_rejected_key_is_not_retried() {
+        let server = MockServer::start(vec![mock::json(
+            401,
+            r#"{"error":{"message":"Incorrect API key"}}"#,
+        )])
+        .await;
+        let error = retrying(&server.url, 3)
+            .complete(&request())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            ai_error(error),
+            AiError::Auth(message) if message == "Incorrect API key"
+        ));
+        assert_eq!(server.requests().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_until_max_retries() {
+        let server = MockServer::start(vec![
+            mock::json(503, r#"{"error":{"message":"overloaded"}}"#),
+            mock::json(502, ""),
+        ])
+        .await;
+        let error = retrying(&server.url, 1)
+            .complete(&request())
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            ai_error(error),
+            AiError::Server { status: 502, .. }
+        ));
+        assert_eq!(server.requests().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn a_server_error_is_retried_until_an_answer() {
+        let server = MockServer::start(vec![mock::json(500, ""), answer("ok")]).await;
+        let response = retrying(&server.url, 1).complete(&request()).await;
+        assert_eq!(response.unwrap().text, "ok");
+        assert_eq!(server.requests().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn a_stream_dropped_after_tokens_is_not_retried() {
+        let server = MockServer::start(vec![mock::dropped(
+            "text/event-stream",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"partial\"}}]}\n\n",
+        )])
+        .await;
+        let mut tokens = Vec::new();
+        let mut on_token = |token: &str| tokens.push(token.to_string());
+        let response = retrying(&server.url, 3)
+            .stream(&request(), &mut on_token)
+            .await;
+        assert!(matches!(
+            ai_error(response.unwrap_err()),
+            AiError::Network(_)
+        ));
+        assert_eq!(tokens, ["partial"]);
+        assert_eq!(server.requests().await.len(), 1);
+    }
+
+    // The lines a body split into `chunks` is read as
+    fn lines(chunks: &[&[u8]]) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut on_line = |line: &str| -> Result<(), Box<dyn Error>> {
+            lines.push(line.to_string());
+            Ok(())
+        };
+        let mut buffer = LineBuffer::default();
+        for chunk in chunks {
+            buffer.push(chunk, &mut on_line).unwrap();
+        }
+        buffer.finish(&mut on_line).unwrap();
+        lines
+    }
+
+    #[test]
+    fn lines_are_joined_across_chunks() {
+        assert_eq!(
+            lines(&[b"data: {\"a\"", b":1}\n\ndata: [DO", b"NE]\n"]),
+            ["data: {\"a\":1}", "data: [DONE]"]
+        );
+    }
+
+    #[test]
+    fn blank_lines_and_carriage_returns_are_dropped() {
+        assert_eq!(
+            lines(&[b"{\"done\":false}\r\n\r\n\n{\"done\":true}\r\n"]),
+            ["{\"done\":false}", "{\"done\":true}"]
+        );
+    }
+
+    #[test]
+    fn a_character_split_between_chunks_stays_whole() {
+        let text = "data: café\n".as_bytes();
+        // 'é' is two bytes, cut between them
+        let cut = text.len() - 2;
+        assert_eq!(lines(&[&text[..cut], &text[cut..]]), ["data: café"]);
+    }
+
+    #[test]
+    fn the_last_line_needs_no_newline() {
+        assert_eq!(
+            lines(&[b"{\"a\":1}\n{\"b\"", b":2}"]),
+            ["{\"a\":1}", "{\"b\":2}"]
+        );
+    }
+
+    #[test]
+    fn an_error_stops_reading() {
+        let mut seen = 0;
+        let mut on_line = |_: &str| -> Result<(), Box<dyn Error>> {
+            seen += 1;
+            Err("stop".into())
+        };
+        let mut buffer = LineBuffer::default();
+        assert!(buffer.push(b"one\ntwo\n", &mut on_line).is_err());
+        assert_eq!(seen, 1);
+    }
+}
@@ -0,0 +1,282 @@
+use colored::*;
+use std::collections::HashMap;
+use std::error::Error;
+
+use super::privacy::Privacy;
+use super::provider::LlmProvider;
+use crate::arguments::models::{Embedding, Todo};
+use crate::configs::AppConfigs;
+use crate::database::DBtodo;
+
+// Texts sent per embeddings request
+const BATCH_SIZE: usize = 64;
+
+const DEFAULT_DESC: &str = "No description provided";
+
+const PRIORITIES: [&str; 4] = ["Low", "Normal", "Medium", "High"];
+
+// SHOW THE TODOS MOST SIMILAR TO ONE
+pub async fn similar(id: i32, limit: usize) -> Result<(), Box<dyn Error>> {
+    let db = DBtodo::new()?;
+    let todo = db
+        .get_todo(id)?
+        .ok_or(format!("No todo found with id: {}", id))?;
+    let todos = db.get_todos()?;
+
+    let privacy = Privacy::load()?;
+    if !privacy.sends("text") {
+        return Err("Finding similar todos needs 'text' in the [PRIVACY] fields".into());
+    }
+    let client = super::client(false)?;
+    let vectors = embed_todos(&db, client.as_ref(), &privacy, &todos).await?;
+    let target = vectors
+        .get(&todo.id)
+        .ok_or("This todo's topic is excluded from AI in the [PRIVACY] settings")?;
+
+    let mut scored = todos
+        .iter()
+        .filter(|other| other.id != todo.id)
+        .filter_map(|other| Some((other, cosine(target, vectors.get(&other.id)?))))
+        .collect::<Vec<(&Todo, f32)>>();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(limit);
+
+    let threshold = threshold()?;
+    println!();
+    println!("Most similar to #{} {}", todo.id, todo.text.bold());
+    println!();
+    if scored.is_empty() {
+        println!("  No other todos to compare with.");
+    }
+    for (other, score) in scored {
+        let percent = format!("{:>3.0}%", score * 100.0);
+        println!(
+            "  {}  #{} {} {}",
+            if threshold > 0.0 && score >= threshold {
+                percent.yellow().bold()
+            } else {
+                percent.normal()
+            },
+            other.id,
+            other.text,
+            format!("({}, {})", other.status, other.priority).bright_black()
+        );
+    }
+    println!();
+    Ok(())
+}
+
+// THE OPEN TODO THAT A NEW ONE MOST LIKELY DUPLICATES, WITH ITS SIMILARITY
+pub async fn find_duplicate(todo: &Todo) -> Result<Option<(Todo, f32)>, Box<dyn Error>> {
+    let threshold = threshold()?;
+    if threshold <= 0.0 {
+        return Ok(None);
+    }
+    let privacy = Privacy::load()?;
+    // Every todo would embed as the same "[hidden]" text and look like a duplicate
+    if !privacy.sends("text") {
+        return Ok(None);
+    }
+    // Todos the model may not see aren't compared either
+    let Some(shared) = privacy.todo(todo) else {
+        return Ok(None);
+    };
+
+    let db = DBtodo::new()?;
+    let open = db
+        .get_todos()?
+        .into_iter()
+        .filter(|todo| todo.status != "Done")
+        .collect::<Vec<Todo>>();
+    if open.is_empty() {
+        return Ok(None);
+    }
+
+    // Without a provider key there's nothing to compare with, adding goes on as usual
+    let Ok(client) = super::client(false) else {
+        return Ok(None);
+    };
+    let vectors = embed_todos(&db, client.as_ref(), &privacy, &open).await?;
+    let new = client
+        .embed(&[embedding_text(&shared)])
+        .await?
+        .pop()
+        .ok_or("The AI provider returned no embedding")?;
+
+    Ok(open
+        .into_iter()
+        .filter_map(|existing| {
+            let score = cosine(&new, vectors.get(&existing.id)?);
+            Some((existing, score))
+        })
+        .filter(|(_, score)| *score >= threshold)
+        .max_by(|a, b| a.1.total_cmp(&b.1)))
+}
+
+// Fold a new todo into the one it duplicates: keep the higher priority and the earlier due
+// date, and note the new text in the description
+pub fn merge(existing: &Todo, new: &Todo) -> Todo {
+    let mut merged = existing.clone();
+
+    let rank = |priority: &str| PRIORITIES.iter().position(|p| *p == priority);
+    if rank(&new.priority) > rank(&existing.priority) {
+        merged.priority = new.priority.clone();
+    }
+
+    let date = |due: &str| chrono::NaiveDate::parse_from_str(due, "%Y-%m-%d").ok();
+    if let Some(new_due) = date(&new.due)
+        && date(&existing.due).is_none_or(|due| new_due < due)
+    {
+        merged.due = new.due.clone();
+    }
+
+    let mut desc = vec![];
+    if existing.desc != DEFAULT_DESC && !existing.desc.trim().is_empty() {
+        desc.push(existing.desc.clone());
+    }
+    desc.push(format!("Merged from a duplicate: {}", new.text));
+    if new.desc != DEFAULT_DESC && !new.desc.trim().is_empty() {
+        desc.push(new.desc.clone());
+    }
+    merged.desc = desc.join("\n");
+    merged
+}
+
+fn threshold() -> Result<f32, Box<dyn Error>> {
+    Ok(AppConfigs::new()?.duplicate_threshold)
+}
+
+// What gets embedded for a todo, the default description would only add noise
+fn embedding_text(todo: &Todo) -> String {
+    if todo.desc == DEFAULT_DESC || todo.desc.trim().is_empty() {
+        todo.text.clone()
+    } else {
+        format!("{}\n{}", todo.text, todo.desc)
+    }
+}
+
+// Vectors of the todos the privacy settings share, only new or edited ones are embedded again
+async fn embed_todos(
+    db: &DBtodo,
+    client: &dyn LlmProvider,
+    privacy: &Privacy,
+    todos: &[Todo],
+) -> Result<HashMap<usize, Vec<f32>>, Box<dyn Error>> {
+    let model = client.embedding_model().to_string();
+    let mut stored = db
+        .get_embeddings(&model)?
+        .into_iter()
+        .map(|embedding| (embedding.todo_id, embedding))
+        .collect::<HashMap<usize, Embedding>>();
+
+    let mut vectors = HashMap::new();
+    let mut missing = Vec::new();
+    for todo in todos {
+        let Some(shared) = privacy.todo(todo) else {
+            continue;
+        };
+        let source = embedding_text(&shared);
+        match stored.remove(&todo.id) {
+            Some(embedding) if embedding.source == source => {
+                vectors.insert(todo.id, embedding.vector);
+            }
+            _ => missing.push(Embedding {
+                todo_id: todo.id,
+                source,
+                vector: Vec::new(),
+            }),
+        }
+    }
+
+    for batch in missing.chunks_mut(BATCH_SIZE) {
+        let texts = batch
+            .iter()
+            .map(|embedding| embedding.source.clone())
+            .collect::<Vec<String>>();
+        for (embedding, vector) in batch.iter_mut().zip(client.embed(&texts).await?) {
+            embedding.vector = vector;
+            db.save_embedding(&model, embedding)?;
+            vectors.insert(embedding.todo_id, embedding.vector.clone());
+        }
+    }
+    Ok(vectors)
+}
+
+// 1.0 for the same direction, 0.0 for unrelated texts
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot = a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 { 0.0 } else { dot / norms }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(text: &str, desc: &str, priority: &str, due: &str) -> Todo {
+        Todo {
+            id: 1,
+            priority: priority.to_string(),
+            topic: "Ops".to_string(),
+            text: text.to_string(),
+            desc: desc.to_string(),
+            date_added: "01-10-26".to_string(),
+            status: "Pending".to_string(),
+            owner: "Alex".to_string(),
+            due: due.to_string(),
+            parent_id: None,
+        }
+    }
+
+    #[test]
+    fn cosine_compares_directions() {
+        assert!((cosine(&[1.0, 2.0], &[2.0, 4.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine(&[1.0, 0.0], &[0.0, 3.0]).abs() < 1e-6);
+        assert!((cosine(&[1.0, 0.0], &[-1.0, 0.0]) + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_of_vectors_that_cant_be_compared_is_zero() {
+        assert_eq!(cosine(&[1.0, 2.0], &[1.0, 2.0, 3.0]), 0.0);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 2.0]), 0.0);
+        assert_eq!(cosine(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn merge_keeps_the_higher_priority_and_earlier_due_date() {
+        let existing = todo("Renew the cert", "Ask ops first", "Normal", "2026-11-01");
+        let new = todo("Renew TLS cert", DEFAULT_DESC, "High", "2026-10-25");
+        let merged = merge(&existing, &new);
+        assert_eq!(merged.priority, "High");
+        assert_eq!(merged.due, "2026-10-25");
+        assert_eq!(merged.text, "Renew the cert");
+        assert_eq!(
+            merged.desc,
+            "Ask ops first\nMerged from a duplicate: Renew TLS cert"
+        );
+
+        // The other way round nothing is lowered or pushed back
+        let merged = merge(&new, &existing);
+        assert_eq!(merged.priority, "High");
+        assert_eq!(merged.due, "2026-10-25");
+    }
+
+    #[test]
+    fn merge_takes_a_due_date_when_there_was_none() {
+        let existing = todo("Renew the cert", DEFAULT_DESC, "Low", "-");
+        let new = todo("Renew TLS cert", "Before the audit", "Low", "2026-10-25");
+        let merged = merge(&existing, &new);
+        assert_eq!(merged.due, "2026-10-25");
+        assert_eq!(
+            merged.desc,
+            "Merged from a duplicate: Renew TLS cert\nBefore the audit"
+        );
+
+        let merged = merge(&new, &existing);
+        assert_eq!(merged.due, "2026-10-25");
+    }
+}
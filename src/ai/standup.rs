@@ -0,0 +1,213 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+use std::error::Error;
+use std::io::{self, Write};
+
+use super::context::TodoContext;
+use super::privacy::Privacy;
+use super::provider::{CompletionRequest, Message};
+use crate::arguments::models::{StatusChange, Todo};
+use crate::configs::AppConfigs;
+use crate::database::DBtodo;
+
+/// Placeholders: {since} and {today} (dates), {format} (markdown or plain text
+/// instructions) and {activity} (status changes and open todos)
+pub const DEFAULT_TEMPLATE: &str = "ROLE: You write standup updates from a todo list.
+Write a standup covering {since} to {today} with exactly three sections: Done, In progress and Blocked.
+RULES:
+- Done: todos that moved to Done in this period
+- In progress: todos that are Ongoing or moved forward in this period
+- Blocked: open todos that are overdue, stuck, or whose description says they wait on something. Write \"Nothing blocked\" when there are none
+- One short line per item, followed by the todo id like (#12)
+- Only use the activity below, don't invent work
+- Answer with the standup only, ready to paste
+{format}
+
+ACTIVITY:
+{activity}";
+
+// WRITE A STANDUP FROM THE STATUS CHANGES SINCE `since`
+pub async fn standup(since: &str, plain: bool, show_request: bool) -> Result<(), Box<dyn Error>> {
+    let start = parse_since(since)?;
+    let today = Local::now().date_naive();
+
+    let db = DBtodo::new()?;
+    let privacy = Privacy::load()?;
+    let todos = privacy.todos(&db.get_todos()?);
+    let changes = privacy.status_changes(
+        &db.get_status_changes(&format!("{} 00:00:00", start))?,
+        &todos,
+    );
+
+    let template = AppConfigs::new()?
+        .standup_template
+        .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+    let format = if plain {
+        "- Plain text only, no markdown: each section name followed by a colon, items start with \"- \""
+    } else {
+        "- Markdown: a \"## \" heading per section, items as \"- \" bullets"
+    };
+    let prompt = template
+        .replace("{since}", &start.format("%A %Y-%m-%d").to_string())
+        .replace("{today}", &today.format("%A %Y-%m-%d").to_string())
+        .replace("{format}", format)
+        .replace(
+            "{activity}",
+            &activity(&changes, &todos, &TodoContext::load()?),
+        );
+
+    let mut print_token = |token: &str| {
+        print!("{}", token);
+        let _ = io::stdout().flush();
+    };
+    super::client(show_request)?
+        .stream(
+            &CompletionRequest {
+                messages: vec![Message::user(prompt)],
+                ..Default::default()
+            },
+            &mut print_token,
+        )
+        .await?;
+    println!();
+    Ok(())
+}
+
+// "today", "yesterday", "week", "3d", "friday" or "2024-05-01" -> first day of the period
+fn parse_since(input: &str) -> Result<NaiveDate, Box<dyn Error>> {
+    let today = Local::now().date_naive();
+    let input = input.trim().to_lowercase();
+
+    let date = match input.as_str() {
+        "today" => today,
+        "yesterday" => today - Duration::days(1),
+        "week" => today - Duration::days(7),
+        _ => {
+            if let Some(days) = input.strip_suffix('d')
+                && let Ok(days) = days.parse::<i64>()
+            {
+                today - Duration::days(days)
+            } else if let Ok(weekday) = input.parse::<Weekday>() {
+                // The last time it was that day, a week ago when it's today
+                let back = (today.weekday().num_days_from_monday() + 7
+                    - weekday.num_days_from_monday())
+                    % 7;
+                today - Duration::days(if back == 0 { 7 } else { back as i64 })
+            } else {
+                NaiveDate::parse_from_str(&input, "%Y-%m-%d").map_err(|_| {
+                    format!(
+                        "Can't read --since '{}', use today, yesterday, week, a number of days like 3d, a weekday or YYYY-MM-DD",
+                        input
+                    )
+                })?
+            }
+        }
+    };
+
+    if date > today {
+        return Err("--since can't be in the future".into());
+    }
+    Ok(date)
+}
+
+// What happened in the period plus what is still open, as text for the prompt
+fn activity(changes: &[StatusChange], todos: &[Todo], context: &TodoContext) -> String {
+    let mut lines = vec!["STATUS CHANGES:".to_string()];
+    if changes.is_empty() {
+        lines.push("- none".to_string());
+    }
+    for change in changes {
+        lines.push(format!(
+            "- {} #{} \"{}\": {} -> {}",
+            change.changed_at,
+            change.todo_id,
+            change.text,
+            change.from.as_deref().unwrap_or("created as"),
+            change.to
+        ));
+    }
+
+    // Open todos go through the context budget, the status changes above always fit a period
+    let open = todos
+        .iter()
+        .filter(|todo| todo.status != "Done")
+        .cloned()
+        .collect::<Vec<Todo>>();
+    lines.push(String::new());
+    lines.push("OPEN TODOS:".to_string());
+    if open.is_empty() {
+        lines.push("- none".to_string());
+    } else {
+        lines.push(context.lines(&open, "", open_todo));
+    }
+
+    lines.join("\n")
+}
+
+// An open todo with what's needed to tell whether it's blocked
+fn open_todo(todo: &Todo) -> String {
+    let today = Local::now().date_naive();
+    let overdue = NaiveDate::parse_from_str(&todo.due, "%Y-%m-%d").is_ok_and(|due| due < today);
+    format!(
+        "- #{} \"{}\" (Status: {}, Priority: {}, Owner: {}, Due: {}{}){}",
+        todo.id,
+        todo.text,
+        todo.status,
+        todo.priority,
+        todo.owner,
+        todo.due,
+        if overdue { ", OVERDUE" } else { "" },
+        if todo.desc.is_empty() {
+            String::new()
+        } else {
+            format!(": {}", todo.desc.replace('\n', " "))
+        }
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn days_ago(days: i64) -> NaiveDate {
+        Local::now().date_naive() - Duration::days(days)
+    }
+
+    #[test]
+    fn relative_periods_count_back_from_today() {
+        assert_eq!(parse_since("today").unwrap(), days_ago(0));
+        assert_eq!(parse_since(" Yesterday ").unwrap(), days_ago(1));
+        assert_eq!(parse_since("week").unwrap(), days_ago(7));
+        assert_eq!(parse_since("3d").unwrap(), days_ago(3));
+        assert_eq!(parse_since("0d").unwrap(), days_ago(0));
+    }
+
+    #[test]
+    fn a_weekday_is_the_last_one_before_today() {
+        let today = Local::now().date_naive();
+        let since = parse_since(&today.weekday().to_string()).unwrap();
+        assert_eq!(since, days_ago(7));
+
+        let yesterday = days_ago(1).weekday().to_string().to_lowercase();
+        assert_eq!(parse_since(&yesterday).unwrap(), days_ago(1));
+    }
+
+    #[test]
+    fn iso_dates_are_read() {
+        assert_eq!(
+            parse_since("2024-05-01").unwrap(),
+            NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn the_future_and_unknown_input_are_rejected() {
+        let tomorrow = (days_ago(0) + Duration::days(1))
+            .format("%Y-%m-%d")
+            .to_string();
+        assert!(parse_since(&tomorrow).is_err());
+        assert!(parse_since("-2d").is_err());
+        assert!(parse_since("last month").is_err());
+        assert!(parse_since("2024-13-01").is_err());
+        assert!(parse_since("").is_err());
+    }
+}
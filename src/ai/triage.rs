@@ -0,0 +1,162 @@
+use colored::*;
+use serde::Deserialize;
+use std::error::Error;
+
+use super::context::TodoContext;
+use super::privacy::Privacy;
+use super::provider::{CompletionRequest, Message};
+use crate::arguments::{add_todo::normalize_priority, confirm::confirm, models::Todo};
+use crate::database::DBtodo;
+
+#[derive(Deserialize, Debug)]
+struct PriorityChange {
+    id: usize,
+    priority: String,
+    #[serde(default)]
+    reason: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct Triage {
+    // Ids of the open todos in the order to work on them
+    #[serde(default)]
+    order: Vec<usize>,
+    #[serde(default)]
+    changes: Vec<PriorityChange>,
+}
+
+// SUGGEST A WORK ORDER AND PRIORITY CHANGES FOR THE OPEN TODOS
+pub async fn triage(yes: bool, show_request: bool) -> Result<(), Box<dyn Error>> {
+    let db = DBtodo::new()?;
+    let open = db
+        .get_todos()?
+        .into_iter()
+        .filter(|todo| todo.status != "Done")
+        .collect::<Vec<Todo>>();
+
+    if open.is_empty() {
+        println!("No open todos to triage.");
+        return Ok(());
+    }
+
+    let shared = Privacy::load()?.todos(&open);
+    let response = super::client(show_request)?
+        .complete(&CompletionRequest {
+            messages: vec![
+                Message::system(system_prompt()),
+                Message::user(format!(
+                    "OPEN TODOS:\n{}",
+                    TodoContext::load()?.open_todos(&shared, "")
+                )),
+            ],
+            json: true,
+            ..Default::default()
+        })
+        .await?;
+
+    let triage: Triage = super::parse_json(&response.text)?;
+    // Only todos that were sent can be moved or changed, shown as they are rather than redacted
+    let sent = open
+        .iter()
+        .filter(|todo| shared.iter().any(|shared| shared.id == todo.id))
+        .collect::<Vec<&Todo>>();
+    let find = |id: usize| sent.iter().copied().find(|todo| todo.id == id);
+
+    // Suggested order, the arrow shows how far each todo moves
+    let order = triage
+        .order
+        .iter()
+        .filter_map(|id| find(*id))
+        .collect::<Vec<&Todo>>();
+    if !order.is_empty() {
+        println!();
+        println!("{}", "🤖 Suggested order".bold());
+        println!();
+        for (position, todo) in order.iter().enumerate() {
+            let current = sent
+                .iter()
+                .position(|t| t.id == todo.id)
+                .unwrap_or(position);
+            let moved = match current as i64 - position as i64 {
+                0 => "  ".normal(),
+                n if n > 0 => format!("↑{}", n).green(),
+                n => format!("↓{}", -n).red(),
+            };
+            println!(
+                "  {:>2}. {:<3} #{} {} {}",
+                position + 1,
+                moved,
+                todo.id,
+                todo.text,
+                format!("({}, due {})", todo.priority, todo.due).bright_black()
+            );
+        }
+    }
+
+    let mut changes = Vec::new();
+    for change in triage.changes {
+        let Some(todo) = find(change.id) else {
+            eprintln!("Skipping a change for unknown todo #{}", change.id);
+            continue;
+        };
+        let priority = match normalize_priority(&change.priority) {
+            Ok(priority) => priority,
+            Err(e) => {
+                eprintln!("Skipping #{}: {}", change.id, e);
+                continue;
+            }
+        };
+        if priority != todo.priority {
+            changes.push((todo, priority, change.reason));
+        }
+    }
+
+    println!();
+    if changes.is_empty() {
+        println!("🤖 No priority changes suggested.");
+        return Ok(());
+    }
+    println!("{}", "🤖 Suggested priority changes".bold());
+
+    let mut applied = 0;
+    for (todo, priority, reason) in changes {
+        println!();
+        println!("  #{} {}", todo.id, todo.text.bold());
+        println!("{}", format!("  - Priority: {}", todo.priority).red());
+        println!("{}", format!("  + Priority: {}", priority).green());
+        if !reason.trim().is_empty() {
+            println!("    {}", reason.trim().bright_black());
+        }
+
+        if !yes && !confirm("  Apply this change?") {
+            continue;
+        }
+        let mut updated = todo.clone();
+        updated.priority = priority;
+        db.edit_todo(&updated)?;
+        applied += 1;
+    }
+
+    println!();
+    println!("✅ Applied {} priority change(s)", applied);
+    Ok(())
+}
+
+fn system_prompt() -> String {
+    format!(
+        r#"ROLE: You triage a todo list: decide what to work on first and which priorities are wrong.
+Answer with a single JSON object and nothing else:
+{{
+  "order": [ids of ALL the open todos, the one to do first at the start],
+  "changes": [
+    {{ "id": 12, "priority": "Low | Normal | Medium | High", "reason": "one short sentence" }}
+  ]
+}}
+RULES:
+- Weigh due dates, current priority, status, owner and age. Overdue and Ongoing work usually comes first
+- Only list a change when the priority should really be different
+- Only use ids from the list
+- Today is {}"#,
+        chrono::Local::now().format("%A %Y-%m-%d")
+    )
+}
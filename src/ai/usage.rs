@@ -0,0 +1,67 @@
+use chrono::{Duration, Local};
+use colored::*;
+use std::error::Error;
+
+use crate::arguments::models::AiUsage;
+use crate::database::DBtodo;
+
+// REPORT AI CALLS AND TOKENS PER DAY AND MODEL FOR THE LAST `days` DAYS
+pub fn usage(days: u32) -> Result<(), Box<dyn Error>> {
+    let since = Local::now().date_naive() - Duration::days(days.saturating_sub(1) as i64);
+    let usage = DBtodo::new()?.get_ai_usage(&since.format("%Y-%m-%d").to_string())?;
+
+    if usage.is_empty() {
+        println!("No AI calls in the last {} day(s).", days);
+        return Ok(());
+    }
+
+    let width = usage
+        .iter()
+        .map(|row| row.model.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+
+    println!();
+    println!(
+        "{}",
+        format!(
+            "  {:<10}  {:<width$}  {:>6}  {:>6}  {:>10}  {:>10}",
+            "Day", "Model", "Calls", "Cached", "Prompt", "Output"
+        )
+        .bold()
+    );
+    for row in &usage {
+        println!(
+            "  {}  {:<width$}  {:>6}  {:>6}  {:>10}  {:>10}",
+            row.day.bright_black(),
+            row.model,
+            row.calls,
+            row.cached,
+            row.prompt_tokens,
+            row.completion_tokens
+        );
+    }
+
+    let total = |field: fn(&AiUsage) -> u64| usage.iter().map(field).sum::<u64>();
+    println!(
+        "{}",
+        format!(
+            "  {:<10}  {:<width$}  {:>6}  {:>6}  {:>10}  {:>10}",
+            "Total",
+            "",
+            total(|row| row.calls),
+            total(|row| row.cached),
+            total(|row| row.prompt_tokens),
+            total(|row| row.completion_tokens)
+        )
+        .bold()
+    );
+    println!();
+    println!(
+        "{}",
+        "Prompt and output are tokens as reported by the provider, cached answers cost none."
+            .bright_black()
+    );
+    Ok(())
+}
@@ -1,35 +1,102 @@
 // In arguments/add_todo.rs
+use crate::ai::similar;
 use crate::database::DBtodo;
 use chrono::Local;
+use colored::*;
 use std::error::Error;
+use std::io::{self, Write};
 
 use super::models::Todo;
 
-pub fn add_todo(
+/// What `add_todo` ended up doing
+pub enum Added {
+    New,
+    /// Folded into this existing todo instead
+    Merged(usize),
+    Cancelled,
+}
+
+pub async fn add_todo(
     text: String,
     topic: Option<String>,
     priority: Option<String>,
     ownder: Option<String>,
     due: Option<String>,
     desc: Option<String>,
-) -> Result<(), Box<dyn Error>> {
+    allow_duplicate: bool,
+) -> Result<Added, Box<dyn Error>> {
+    let new_todo = build_todo(text, topic, priority, ownder, due, desc)?;
+
+    let db = DBtodo::new()?;
+    if !allow_duplicate {
+        // Adding still works when the AI provider can't be reached
+        match similar::find_duplicate(&new_todo).await {
+            Ok(Some((existing, score))) => match ask_duplicate(&existing, score) {
+                'm' => {
+                    db.edit_todo(&similar::merge(&existing, &new_todo))?;
+                    return Ok(Added::Merged(existing.id));
+                }
+                'c' => return Ok(Added::Cancelled),
+                _ => {}
+            },
+            Ok(None) => {}
+            Err(e) => println!(
+                "{}",
+                format!(
+                    "Duplicate check skipped, embedding the todo failed: {}. Check embedding_model and the API key in [AI], or add with --allow-duplicate.",
+                    e
+                )
+                .bright_black()
+            ),
+        }
+    }
+
+    db.add_todo(&new_todo)?;
+    Ok(Added::New)
+}
+
+// 'a' (add anyway, the default), 'm' (merge) or 'c' (cancel)
+fn ask_duplicate(existing: &Todo, score: f32) -> char {
+    println!(
+        "⚠️  This looks like #{} {} {}",
+        existing.id,
+        existing.text.bold(),
+        format!("({:.0}% similar, {})", score * 100.0, existing.status).bright_black()
+    );
+    print!(
+        "[a]dd anyway, [m]erge into #{}, or [c]ancel? [A/m/c] ",
+        existing.id
+    );
+    let mut answer = String::new();
+    if io::stdout().flush().is_err() || io::stdin().read_line(&mut answer).is_err() {
+        return 'a';
+    }
+    match answer.trim().to_lowercase().as_str() {
+        "m" | "merge" => 'm',
+        "c" | "cancel" => 'c',
+        _ => 'a',
+    }
+}
+
+// VALIDATE THE FIELDS AND FILL IN THE DEFAULTS FOR A NEW TODO
+pub fn build_todo(
+    text: String,
+    topic: Option<String>,
+    priority: Option<String>,
+    ownder: Option<String>,
+    due: Option<String>,
+    desc: Option<String>,
+) -> Result<Todo, Box<dyn Error>> {
+    let text = text.trim().to_string();
+    if text.is_empty() {
+        return Err("The todo text can't be empty.".into());
+    }
+
     let date_added = Local::now().format("%d-%m-%y").to_string();
     let topic = topic.unwrap_or_else(|| "General".to_string());
 
     // handle priority
-    let priority = priority.unwrap_or_else(|| "normal".to_string());
-    if priority != "normal" && priority != "high" && priority != "low" && priority != "medium" {
-        return Err("Priority must be 'medium', 'high', or 'low'.".into());
-    }
-    let priority = priority.to_lowercase();
-    // Uppercase only the first letter
-    let priority = priority
-        .chars()
-        .next()
-        .unwrap()
-        .to_ascii_uppercase()
-        .to_string()
-        + &priority[1..];
+    let priority = normalize_priority(&priority.unwrap_or_else(|| "normal".to_string()))?;
 
     let owner = ownder.unwrap_or_else(|| "You".to_string());
 
@@ -38,9 +105,7 @@ pub fn add_todo(
 
     let desc = desc.unwrap_or_else(|| "No description provided".to_string());
 
-    let db = DBtodo::new()?;
-
-    let new_todo = Todo {
+    Ok(Todo {
         id: 0, // Will be auto-incremented by SQLite
         priority,
         topic,
@@ -50,8 +115,22 @@ pub fn add_todo(
         due: due_date,
         status: "Pending".to_string(),
         owner,
-    };
+        parent_id: None,
+    })
+}
 
-    db.add_todo(&new_todo)?;
-    Ok(())
+// Accepts any casing and returns e.g. "High"
+pub fn normalize_priority(priority: &str) -> Result<String, Box<dyn Error>> {
+    let priority = priority.trim().to_lowercase();
+    if priority != "normal" && priority != "high" && priority != "low" && priority != "medium" {
+        return Err("Priority must be 'normal', 'medium', 'high', or 'low'.".into());
+    }
+    // Uppercase only the first letter
+    Ok(priority
+        .chars()
+        .next()
+        .unwrap()
+        .to_ascii_uppercase()
+        .to_string()
+        + &priority[1..])
 }
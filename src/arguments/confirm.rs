@@ -0,0 +1,16 @@
+use std::io::{self, Write};
+
+// ASK A YES/NO QUESTION ON THE TERMINAL, DEFAULTS TO NO
+pub fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    if io::stdout().flush().is_err() {
+        return false;
+    }
+
+    let mut answer = String::new();
+    if io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
@@ -2,7 +2,7 @@ use std::error::Error;
 
 use crate::database::DBtodo;
 
-pub fn remove_todo(id: i32) -> Result<(), Box<dyn Error>> {
+pub fn remove_todo(id: i32) -> Result<bool, Box<dyn Error>> {
     let db = DBtodo::new()?;
 
     db.delete_todo(id)
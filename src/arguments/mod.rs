@@ -1,4 +1,5 @@
 pub mod add_todo;
+pub mod confirm;
 pub mod delete_todo;
 pub mod models;
 pub mod print;
@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 #[derive(Debug, Clone)]
 pub struct Todo {
@@ -11,6 +11,53 @@ pub struct Todo {
     pub status: String,
     pub owner: String,
     pub due: String,
+    /// Set when the todo is a subtask of another one
+    pub parent_id: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    pub id: i64,
+    pub title: String,
+    pub updated_at: String,
+    pub messages: usize,
+}
+
+/// A saved chat message, `role` is "user" or "assistant"
+#[derive(Debug, Clone)]
+pub struct ChatMessage {
+    pub role: String,
+    pub content: String,
+}
+
+/// A todo's embedding, `source` is the text it was computed from
+#[derive(Debug, Clone)]
+pub struct Embedding {
+    pub todo_id: usize,
+    pub source: String,
+    pub vector: Vec<f32>,
+}
+
+/// AI calls and tokens of one model on one day
+#[derive(Debug, Clone)]
+pub struct AiUsage {
+    pub day: String,
+    pub model: String,
+    pub calls: u64,
+    /// Calls answered from the local cache, they cost nothing
+    pub cached: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct StatusChange {
+    pub todo_id: usize,
+    pub text: String,
+    /// None when the todo was created with this status
+    pub from: Option<String>,
+    pub to: String,
+    pub changed_at: String,
 }
 
 #[derive(Debug, Parser)]
@@ -43,6 +90,10 @@ pub struct Cli {
     #[arg(short = 'p', long, value_name = "PRIORITY", requires = "add")]
     pub priority: Option<String>,
 
+    /// Skip the check for similar open todos (requires --add)
+    #[arg(long, requires = "add")]
+    pub allow_duplicate: bool,
+
     /// Print all todos to the console
     #[arg(short = 'P', long)]
     pub print: bool,
@@ -79,14 +130,22 @@ pub struct Cli {
     #[arg(short = 'd', long, value_name = "DUE DATE", requires = "add")]
     pub due: Option<String>,
 
-    /// pass the API key credentrials
+    /// pass the API key credentrials (ends up in your shell history, prefer --apikey-stdin)
     #[arg(short = 'k', long, value_name = "API_KEY")]
     pub apikey: Option<String>,
 
+    /// Read the API key from stdin and save it to the key file
+    #[arg(long, conflicts_with = "apikey")]
+    pub apikey_stdin: bool,
+
     /// ASK GEMINI
     #[arg(short = 'A', long, value_name = "PROMPT")]
     pub prompt: Option<String>,
 
+    /// Print the exact request that would be sent to the AI provider instead of sending it
+    #[arg(long, requires = "prompt")]
+    pub show_request: bool,
+
     /// Version Check
     #[arg(short, long)]
     pub release: bool,
@@ -94,4 +153,99 @@ pub struct Cli {
     /// Clear the databse
     #[arg(short, long)]
     pub flush: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// AI powered commands
+    Ai {
+        /// Print the exact requests that would be sent to the AI provider instead of sending them
+        #[arg(long)]
+        show_request: bool,
+
+        #[command(subcommand)]
+        command: AiCommand,
+    },
+
+    /// List the todos most similar to one, using embeddings from the AI provider
+    Similar {
+        #[arg(value_name = "ID")]
+        id: i32,
+
+        /// How many todos to show
+        #[arg(short = 'n', long, default_value_t = 5)]
+        limit: usize,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum AiCommand {
+    /// Create a todo from a sentence, e.g. "remind alice to renew the cert by friday"
+    Add {
+        #[arg(value_name = "TEXT", num_args = 1.., required = true)]
+        text: Vec<String>,
+
+        /// Add the todo without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Break a todo into smaller steps and add the ones you pick as subtasks
+    Breakdown {
+        #[arg(value_name = "ID")]
+        id: i32,
+    },
+
+    /// Let the AI read and change your todos, e.g. "mark everything about the launch as done"
+    Agent {
+        #[arg(value_name = "PROMPT", num_args = 1.., required = true)]
+        prompt: Vec<String>,
+
+        /// Apply every change without asking
+        #[arg(short, long)]
+        yes: bool,
+
+        /// Print the planned changes without applying them
+        #[arg(long, conflicts_with = "yes")]
+        dry_run: bool,
+    },
+
+    /// Chat with the AI about your todos
+    Chat {
+        /// Pick up a saved conversation
+        #[arg(short, long, value_name = "ID")]
+        resume: Option<i64>,
+
+        /// List the saved conversations
+        #[arg(short, long, conflicts_with = "resume")]
+        list: bool,
+    },
+
+    /// Write a standup (done / in progress / blocked) from recent status changes
+    Standup {
+        /// today, yesterday, week, a number of days like 3d, or a date (YYYY-MM-DD)
+        #[arg(short, long, value_name = "WHEN", default_value = "yesterday")]
+        since: String,
+
+        /// Plain text instead of markdown
+        #[arg(long)]
+        plain: bool,
+    },
+
+    /// Suggest what to work on first and which priorities to change
+    Triage {
+        /// Apply every priority change without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Show AI calls and tokens per day and model
+    Usage {
+        /// How many days back to report
+        #[arg(short, long, default_value_t = 30)]
+        days: u32,
+    },
 }
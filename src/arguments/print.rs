@@ -10,6 +10,9 @@ pub fn print_todos() {
         println!("Text: {}", todo.text);
         println!("Date Added: {}", todo.date_added);
         println!("Status: {}", todo.status);
+        if let Some(parent_id) = todo.parent_id {
+            println!("Subtask of: {}", parent_id);
+        }
         println!();
     }
 }
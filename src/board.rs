@@ -0,0 +1,123 @@
+use ratatui::prelude::Stylize;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::App;
+use crate::arguments::models::Todo;
+
+// Rows a card takes, borders included
+const CARD_HEIGHT: u16 = 4;
+
+/// The board's columns: the configured statuses, then any other status a todo has, each with
+/// the indexes of its todos in `todos`
+pub fn columns(statuses: &[String], todos: &[Todo]) -> Vec<(String, Vec<usize>)> {
+    let mut columns = statuses
+        .iter()
+        .map(|status| (status.clone(), Vec::new()))
+        .collect::<Vec<(String, Vec<usize>)>>();
+
+    for (index, todo) in todos.iter().enumerate() {
+        match columns
+            .iter_mut()
+            .find(|(status, _)| status.eq_ignore_ascii_case(&todo.status))
+        {
+            Some((_, cards)) => cards.push(index),
+            None => columns.push((todo.status.clone(), vec![index])),
+        }
+    }
+    columns
+}
+
+// KANBAN BOARD
+pub fn draw_board(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let background = theme.background;
+    let accent = theme.accent;
+    let border = theme.border;
+    let text_primary = theme.text_primary;
+    let text_secondary = theme.text_secondary;
+
+    let block = Block::default()
+        .title(" RustyDO Board ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border))
+        .style(Style::default().bg(background));
+    let inner_area = block.inner(area);
+    f.render_widget(block, area);
+
+    let columns = columns(&app.statuses, &app.todos);
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Ratio(1, columns.len() as u32);
+            columns.len()
+        ])
+        .split(inner_area);
+
+    let selected = app.state.selected();
+    for (column, ((status, cards), column_area)) in columns.iter().zip(areas.iter()).enumerate() {
+        let focused = column == app.board_column;
+        let column_block = Block::default()
+            .title(format!(" {} ({}) ", status.to_uppercase(), cards.len()))
+            .borders(Borders::ALL)
+            .border_style(if focused {
+                Style::default().fg(accent).add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(text_secondary)
+            });
+        let cards_area = column_block.inner(*column_area);
+        f.render_widget(column_block, *column_area);
+
+        // Scroll so the selected card stays in view
+        let fits = (cards_area.height / CARD_HEIGHT).max(1) as usize;
+        let position = cards
+            .iter()
+            .position(|index| Some(*index) == selected)
+            .unwrap_or(0);
+        let skip = (position + 1).saturating_sub(fits);
+
+        for (row, index) in cards.iter().skip(skip).take(fits).enumerate() {
+            let todo = &app.todos[*index];
+            let card_area = Rect {
+                y: cards_area.y + row as u16 * CARD_HEIGHT,
+                height: CARD_HEIGHT.min(cards_area.height),
+                ..cards_area
+            };
+            let is_selected = focused && Some(*index) == selected;
+            let card = Paragraph::new(vec![
+                Line::from(vec![
+                    if app.marked.contains(&todo.id) {
+                        format!("● #{} ", todo.id).fg(accent).bold()
+                    } else {
+                        format!("#{} ", todo.id).fg(accent)
+                    },
+                    todo.text.clone().bold().fg(text_primary),
+                ]),
+                Line::from(vec![
+                    todo.priority.clone().fg(theme.priority(&todo.priority)),
+                    format!("  {}  {}", todo.due, todo.owner).fg(text_secondary),
+                ]),
+            ])
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(if is_selected {
+                        text_primary
+                    } else {
+                        theme.muted
+                    }))
+                    .style(if is_selected {
+                        Style::default().bg(theme.highlight)
+                    } else {
+                        Style::default()
+                    }),
+            );
+            f.render_widget(card, card_area);
+        }
+    }
+}
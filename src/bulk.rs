@@ -0,0 +1,136 @@
+use chrono::NaiveDate;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::error::Error;
+
+use crate::arguments::models::Todo;
+use crate::database::DBtodo;
+
+/// A change applied to every marked todo at once
+#[derive(Debug, Clone)]
+pub enum BulkAction {
+    Status(String),
+    Delete,
+    Owner(String),
+    Topic(String),
+    /// "-" clears the due date
+    Due(String),
+}
+
+impl BulkAction {
+    // "Mark 3 todos as Done?"
+    pub fn question(&self, count: usize) -> String {
+        let todos = format!("{} todo{}", count, if count == 1 { "" } else { "s" });
+        match self {
+            BulkAction::Status(status) => format!("Mark {} as {}?", todos, status),
+            BulkAction::Delete => format!("Delete {}?", todos),
+            BulkAction::Owner(owner) => format!("Reassign {} to {}?", todos, owner),
+            BulkAction::Topic(topic) => format!("Move {} to topic {}?", todos, topic),
+            BulkAction::Due(due) if due == "-" => format!("Clear the due date of {}?", todos),
+            BulkAction::Due(due) => format!("Set the due date of {} to {}?", todos, due),
+        }
+    }
+
+    // All the todos change or none do
+    pub fn apply(&self, todos: &[Todo]) -> Result<(), Box<dyn Error>> {
+        let db = DBtodo::new()?;
+        if let BulkAction::Delete = self {
+            let ids = todos.iter().map(|todo| todo.id).collect::<Vec<usize>>();
+            return db.delete_todos(&ids);
+        }
+
+        let edited = todos
+            .iter()
+            .cloned()
+            .map(|mut todo| {
+                match self {
+                    BulkAction::Status(status) => todo.status = status.clone(),
+                    BulkAction::Owner(owner) => todo.owner = owner.clone(),
+                    BulkAction::Topic(topic) => todo.topic = topic.clone(),
+                    BulkAction::Due(due) => todo.due = due.clone(),
+                    BulkAction::Delete => {}
+                }
+                todo
+            })
+            .collect::<Vec<Todo>>();
+        db.edit_todos(&edited)
+    }
+}
+
+/// A bulk change waiting for y/n
+#[derive(Debug)]
+pub struct Confirm {
+    pub action: BulkAction,
+    pub ids: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PromptField {
+    Owner,
+    Topic,
+    Due,
+}
+
+impl PromptField {
+    pub fn title(&self) -> &'static str {
+        match self {
+            PromptField::Owner => " Reassign owner ",
+            PromptField::Topic => " Change topic ",
+            PromptField::Due => " Set due date (YYYY-MM-DD, empty to clear) ",
+        }
+    }
+}
+
+/// What a key press in the prompt asks the TUI to do
+pub enum PromptAction {
+    Continue,
+    Cancel,
+    Submit(BulkAction),
+}
+
+/// The one-line input for a new owner, topic or due date
+#[derive(Debug)]
+pub struct BulkPrompt {
+    pub field: PromptField,
+    pub value: String,
+    pub error: Option<String>,
+}
+
+impl BulkPrompt {
+    pub fn new(field: PromptField) -> Self {
+        Self {
+            field,
+            value: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PromptAction {
+        match key.code {
+            KeyCode::Esc => return PromptAction::Cancel,
+            KeyCode::Enter => match self.action() {
+                Ok(action) => return PromptAction::Submit(action),
+                Err(e) => self.error = Some(e),
+            },
+            KeyCode::Backspace => {
+                self.value.pop();
+            }
+            KeyCode::Char(c) => self.value.push(c),
+            _ => {}
+        }
+        PromptAction::Continue
+    }
+
+    fn action(&self) -> Result<BulkAction, String> {
+        let value = self.value.trim().to_string();
+        match self.field {
+            PromptField::Due if value.is_empty() => Ok(BulkAction::Due("-".to_string())),
+            PromptField::Due => match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+                Ok(_) => Ok(BulkAction::Due(value)),
+                Err(_) => Err(format!("'{}' isn't a date, use YYYY-MM-DD.", value)),
+            },
+            _ if value.is_empty() => Err("The value can't be empty.".to_string()),
+            PromptField::Owner => Ok(BulkAction::Owner(value)),
+            PromptField::Topic => Ok(BulkAction::Topic(value)),
+        }
+    }
+}
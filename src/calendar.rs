@@ -0,0 +1,176 @@
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use ratatui::prelude::Stylize;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+};
+
+use crate::App;
+use crate::arguments::models::Todo;
+
+pub fn due_date(todo: &Todo) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(&todo.due, "%Y-%m-%d").ok()
+}
+
+/// Indexes in `todos` of the todos due on `day`
+pub fn due_on(todos: &[Todo], day: NaiveDate) -> Vec<usize> {
+    todos
+        .iter()
+        .enumerate()
+        .filter(|(_, todo)| due_date(todo) == Some(day))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+// First day shown for a month: the Monday on or before the 1st
+fn grid_start(day: NaiveDate) -> NaiveDate {
+    let first = day.with_day(1).unwrap_or(day);
+    first - Duration::days(first.weekday().num_days_from_monday() as i64)
+}
+
+// CALENDAR OF DUE DATES
+pub fn draw_calendar(f: &mut Frame, area: Rect, app: &App) {
+    let theme = &app.theme;
+    let background = theme.background;
+    let accent = theme.accent;
+    let border = theme.border;
+    let text_primary = theme.text_primary;
+    let text_secondary = theme.text_secondary;
+    let overdue = theme.error;
+
+    let today = Local::now().date_naive();
+    let selected_day = app.calendar_day;
+
+    let block = Block::default()
+        .title(format!(" {} ", selected_day.format("%B %Y")))
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border))
+        .style(Style::default().bg(background));
+    let inner_area = block.inner(area);
+    f.render_widget(block, area);
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+        .split(inner_area);
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1),
+            Constraint::Ratio(1, 6),
+            Constraint::Ratio(1, 6),
+            Constraint::Ratio(1, 6),
+            Constraint::Ratio(1, 6),
+            Constraint::Ratio(1, 6),
+            Constraint::Ratio(1, 6),
+        ])
+        .split(columns[0]);
+    let week = |row: Rect| {
+        Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(vec![Constraint::Ratio(1, 7); 7])
+            .split(row)
+    };
+
+    for (cell, name) in week(rows[0])
+        .iter()
+        .zip(["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"])
+    {
+        f.render_widget(Paragraph::new(name.bold().fg(accent)).centered(), *cell);
+    }
+
+    let start = grid_start(selected_day);
+    for (row, row_area) in rows.iter().skip(1).enumerate() {
+        for (column, cell) in week(*row_area).iter().enumerate() {
+            let day = start + Duration::days((row * 7 + column) as i64);
+            let due = due_on(&app.todos, day);
+            let open = due
+                .iter()
+                .filter(|index| app.todos[**index].status != "Done")
+                .count();
+
+            let in_month = day.month() == selected_day.month();
+            let number_color = if !in_month {
+                theme.muted
+            } else if day < today && open > 0 {
+                overdue
+            } else {
+                text_primary
+            };
+            let mut number = day.day().to_string().fg(number_color);
+            if day == today {
+                number = number.bold().underlined();
+            }
+
+            let count = match due.len() {
+                0 => "".into(),
+                n if day < today && open > 0 => format!("● {}", n).fg(overdue),
+                n => format!("● {}", n).fg(accent),
+            };
+
+            let is_selected = day == selected_day;
+            f.render_widget(
+                Paragraph::new(vec![Line::from(number), Line::from(count)]).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(if is_selected {
+                            Style::default()
+                                .fg(text_primary)
+                                .add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default().fg(theme.muted)
+                        })
+                        .style(if is_selected {
+                            Style::default().bg(theme.highlight)
+                        } else {
+                            Style::default()
+                        }),
+                ),
+                *cell,
+            );
+        }
+    }
+
+    // The selected day's todos
+    let due = due_on(&app.todos, selected_day);
+    let selected = app.state.selected();
+    let mut lines = Vec::new();
+    if due.is_empty() {
+        lines.push(Line::from("Nothing due".italic().fg(text_secondary)));
+    }
+    for index in due {
+        let todo = &app.todos[index];
+        let line = Line::from(vec![
+            if app.marked.contains(&todo.id) {
+                format!("● #{} ", todo.id).fg(accent).bold()
+            } else {
+                format!("#{} ", todo.id).fg(accent)
+            },
+            todo.text.clone().fg(text_primary),
+            format!(" ({}, {})", todo.status, todo.priority).fg(text_secondary),
+        ]);
+        lines.push(if Some(index) == selected {
+            line.style(
+                Style::default()
+                    .bg(theme.selection)
+                    .fg(theme.selection_text)
+                    .add_modifier(Modifier::BOLD),
+            )
+        } else {
+            line
+        });
+    }
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title(format!(" {} ", selected_day.format("%A %-d %B")))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(text_secondary)),
+        ),
+        columns[1],
+    );
+}
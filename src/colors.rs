@@ -1,5 +1,258 @@
-pub enum AppColors {
-    Primary,
-    Secondary,
-    Tertiary,
+use ratatui::style::Color;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Colours of the TUI, picked with `theme` in the `[TUI]` section of config.toml
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub background: Color,
+    pub accent: Color,
+    pub border: Color,
+    pub text_primary: Color,
+    pub text_secondary: Color,
+    /// Dim borders, placeholders and days outside the month
+    pub muted: Color,
+    /// Background of the selected card or calendar day
+    pub highlight: Color,
+    /// Background and text of the selected table row
+    pub selection: Color,
+    pub selection_text: Color,
+    /// Background of marked rows
+    pub marked: Color,
+    pub success: Color,
+    pub error: Color,
+    /// Border of the confirmation dialogs
+    pub warning: Color,
+    // Keyed by the lowercased status or priority
+    statuses: HashMap<String, Color>,
+    priorities: HashMap<String, Color>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::purple()
+    }
+}
+
+impl Theme {
+    /// A built-in theme by name: purple, light, high-contrast, solarized or basic
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "purple" => Some(Self::purple()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "solarized" => Some(Self::solarized()),
+            "basic" => Some(Self::basic()),
+            _ => None,
+        }
+    }
+
+    pub fn status(&self, status: &str) -> Color {
+        self.statuses
+            .get(&status.to_lowercase())
+            .copied()
+            .unwrap_or(self.text_primary)
+    }
+
+    // Priorities without a colour of their own look like Low
+    pub fn priority(&self, priority: &str) -> Color {
+        self.priorities
+            .get(&priority.to_lowercase())
+            .or_else(|| self.priorities.get("low"))
+            .copied()
+            .unwrap_or(self.text_secondary)
+    }
+
+    pub fn set_status(&mut self, status: &str, color: Color) {
+        self.statuses.insert(status.to_lowercase(), color);
+    }
+
+    pub fn set_priority(&mut self, priority: &str, color: Color) {
+        self.priorities.insert(priority.to_lowercase(), color);
+    }
+
+    /// Override one of the named colours, unknown names are ignored
+    pub fn set(&mut self, name: &str, color: Color) {
+        let slot = match name {
+            "background" => &mut self.background,
+            "accent" => &mut self.accent,
+            "border" => &mut self.border,
+            "text_primary" => &mut self.text_primary,
+            "text_secondary" => &mut self.text_secondary,
+            "muted" => &mut self.muted,
+            "highlight" => &mut self.highlight,
+            "selection" => &mut self.selection,
+            "selection_text" => &mut self.selection_text,
+            "marked" => &mut self.marked,
+            "success" => &mut self.success,
+            "error" => &mut self.error,
+            "warning" => &mut self.warning,
+            _ => return,
+        };
+        *slot = color;
+    }
+
+    // The status and priority colours shared by every theme, from its own palette
+    fn with_defaults(mut self, ongoing: Color, priorities: [Color; 3]) -> Self {
+        for status in ["done", "completed"] {
+            self.statuses.insert(status.to_string(), self.success);
+        }
+        for status in ["pending", "backlog"] {
+            self.statuses.insert(status.to_string(), self.error);
+        }
+        self.statuses.insert("ongoing".to_string(), ongoing);
+        self.statuses.insert("in progress".to_string(), ongoing);
+        self.statuses.insert("planned".to_string(), self.accent);
+
+        let [high, medium, low] = priorities;
+        self.priorities.insert("high".to_string(), high);
+        self.priorities.insert("medium".to_string(), medium);
+        self.priorities.insert("low".to_string(), low);
+        self
+    }
+
+    // The original palette
+    fn purple() -> Self {
+        Self {
+            background: Color::Rgb(25, 15, 30),        // Deep purple
+            accent: Color::Rgb(150, 80, 220),          // Vibrant purple
+            border: Color::Rgb(180, 140, 220),         // Soft lavender
+            text_primary: Color::Rgb(230, 220, 240),   // Light lavender
+            text_secondary: Color::Rgb(200, 180, 220), // Muted lavender
+            muted: Color::Rgb(90, 60, 110),
+            highlight: Color::Rgb(50, 30, 60), // Darker purple
+            selection: Color::Rgb(120, 80, 190),
+            selection_text: Color::Rgb(255, 255, 255),
+            marked: Color::Rgb(45, 25, 60),
+            success: Color::Rgb(120, 220, 150), // Soft green
+            error: Color::Rgb(220, 100, 120),   // Soft red
+            warning: Color::Rgb(200, 100, 220), // Bright purple
+            statuses: HashMap::new(),
+            priorities: HashMap::new(),
+        }
+        .with_defaults(
+            Color::Rgb(220, 180, 100), // Amber
+            [
+                Color::Rgb(220, 80, 150),  // Pinkish purple
+                Color::Rgb(180, 120, 220), // Medium purple
+                Color::Rgb(120, 80, 200),  // Deep purple
+            ],
+        )
+    }
+
+    fn light() -> Self {
+        Self {
+            background: Color::Rgb(250, 248, 252),
+            accent: Color::Rgb(110, 60, 180),
+            border: Color::Rgb(150, 120, 190),
+            text_primary: Color::Rgb(40, 30, 50),
+            text_secondary: Color::Rgb(90, 80, 110),
+            muted: Color::Rgb(180, 170, 195),
+            highlight: Color::Rgb(232, 222, 246),
+            selection: Color::Rgb(110, 60, 180),
+            selection_text: Color::Rgb(255, 255, 255),
+            marked: Color::Rgb(240, 230, 250),
+            success: Color::Rgb(30, 140, 70),
+            error: Color::Rgb(200, 50, 70),
+            warning: Color::Rgb(180, 60, 160),
+            statuses: HashMap::new(),
+            priorities: HashMap::new(),
+        }
+        .with_defaults(
+            Color::Rgb(190, 120, 0),
+            [
+                Color::Rgb(200, 40, 110),
+                Color::Rgb(150, 80, 200),
+                Color::Rgb(90, 90, 160),
+            ],
+        )
+    }
+
+    fn high_contrast() -> Self {
+        Self {
+            background: Color::Rgb(0, 0, 0),
+            accent: Color::Rgb(255, 255, 0),
+            border: Color::Rgb(255, 255, 255),
+            text_primary: Color::Rgb(255, 255, 255),
+            text_secondary: Color::Rgb(220, 220, 220),
+            muted: Color::Rgb(150, 150, 150),
+            highlight: Color::Rgb(50, 50, 50),
+            selection: Color::Rgb(255, 255, 0),
+            selection_text: Color::Rgb(0, 0, 0),
+            marked: Color::Rgb(0, 0, 130),
+            success: Color::Rgb(0, 255, 0),
+            error: Color::Rgb(255, 70, 70),
+            warning: Color::Rgb(255, 0, 255),
+            statuses: HashMap::new(),
+            priorities: HashMap::new(),
+        }
+        .with_defaults(
+            Color::Rgb(0, 210, 255),
+            [
+                Color::Rgb(255, 70, 70),
+                Color::Rgb(255, 255, 0),
+                Color::Rgb(0, 210, 255),
+            ],
+        )
+    }
+
+    // Solarized dark
+    fn solarized() -> Self {
+        Self {
+            background: Color::Rgb(0, 43, 54),         // base03
+            accent: Color::Rgb(38, 139, 210),          // blue
+            border: Color::Rgb(88, 110, 117),          // base01
+            text_primary: Color::Rgb(147, 161, 161),   // base1
+            text_secondary: Color::Rgb(131, 148, 150), // base0
+            muted: Color::Rgb(88, 110, 117),           // base01
+            highlight: Color::Rgb(7, 54, 66),          // base02
+            selection: Color::Rgb(38, 139, 210),       // blue
+            selection_text: Color::Rgb(253, 246, 227), // base3
+            marked: Color::Rgb(7, 54, 66),             // base02
+            success: Color::Rgb(133, 153, 0),          // green
+            error: Color::Rgb(220, 50, 47),            // red
+            warning: Color::Rgb(211, 54, 130),         // magenta
+            statuses: HashMap::new(),
+            priorities: HashMap::new(),
+        }
+        .with_defaults(
+            Color::Rgb(181, 137, 0), // yellow
+            [
+                Color::Rgb(211, 54, 130),  // magenta
+                Color::Rgb(203, 75, 22),   // orange
+                Color::Rgb(108, 113, 196), // violet
+            ],
+        )
+    }
+
+    // The 16 ANSI colours, for terminals without truecolor
+    fn basic() -> Self {
+        Self {
+            background: Color::Reset,
+            accent: Color::Magenta,
+            border: Color::Gray,
+            text_primary: Color::White,
+            text_secondary: Color::Gray,
+            muted: Color::DarkGray,
+            highlight: Color::DarkGray,
+            selection: Color::Magenta,
+            selection_text: Color::White,
+            marked: Color::Blue,
+            success: Color::Green,
+            error: Color::Red,
+            warning: Color::LightMagenta,
+            statuses: HashMap::new(),
+            priorities: HashMap::new(),
+        }
+        .with_defaults(Color::Yellow, [Color::LightRed, Color::Yellow, Color::Blue])
+    }
+}
+
+/// A colour from the config: a name ("red", "lightblue"), "#rrggbb" or a 0-255 index
+pub fn parse_color(value: &toml::Value) -> Option<Color> {
+    match value {
+        toml::Value::String(color) => Color::from_str(color).ok(),
+        toml::Value::Integer(index) => u8::try_from(*index).ok().map(Color::Indexed),
+        _ => None,
+    }
 }
@@ -2,10 +2,80 @@ use anyhow::{Context, Result};
 use directories::BaseDirs;
 use std::path::PathBuf;
 
+use crate::colors::{Theme, parse_color};
+use crate::keymap::Keymap;
+
 pub struct AppConfigs {
-    pub model: String,
+    /// LLM backend: "gemini", "openai" (any OpenAI-compatible server) or "ollama"
+    pub provider: String,
+    /// Model name, each provider has its own default
+    pub model: Option<String>,
+    /// Model for `similar` and the duplicate check, each provider has its own default
+    pub embedding_model: Option<String>,
+    /// Override the provider's endpoint, e.g. a self-hosted server
+    pub base_url: Option<String>,
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: u64,
+    /// Retries after a rate limit (429) or server error (5xx)
+    pub max_retries: u32,
+    /// Estimated tokens of todos sent with a prompt, the least relevant are summarised
+    pub context_tokens: usize,
+    /// How long an answer is reused for the same request, 0 turns the cache off
+    pub cache_ttl_secs: u64,
+    /// Cosine similarity from which a new todo counts as a duplicate, 0 turns the check off
+    pub duplicate_threshold: f32,
+    /// Environment variable checked first for the API key
+    pub api_key_env: String,
+    /// Shell command whose stdout is the API key (e.g. `pass show gemini`)
+    pub api_key_cmd: Option<String>,
+    /// File holding the API key, must only be readable by the owner
+    pub api_key_file: PathBuf,
+    /// Prompt for `ai standup`, see `ai::standup::DEFAULT_TEMPLATE` for the placeholders
+    pub standup_template: Option<String>,
+    pub privacy: PrivacyConfigs,
+    pub tui: TuiConfigs,
+}
+
+/// The `[PRIVACY]` section: what todo data may leave the machine
+pub struct PrivacyConfigs {
+    /// Todo fields sent to the AI provider
+    pub fields: Vec<String>,
+    /// Todos in these topics are never sent
+    pub exclude_topics: Vec<String>,
+    /// Regexes replaced with `[redacted]` in everything sent
+    pub redact: Vec<String>,
+    pub max_todos: Option<usize>,
 }
 
+/// The `[TUI]` section
+pub struct TuiConfigs {
+    /// Columns of the kanban board, in order
+    pub statuses: Vec<String>,
+    /// The built-in theme named by `theme`, with the `[TUI.colors]` overrides
+    pub theme: Theme,
+    /// The default keys with the `[TUI.keys]` overrides
+    pub keymap: Keymap,
+    /// `[TUI.keys]` entries that couldn't be used, shown in the TUI's status bar
+    pub warnings: Vec<String>,
+}
+
+impl Default for TuiConfigs {
+    fn default() -> Self {
+        Self {
+            statuses: DEFAULT_STATUSES.iter().map(|s| s.to_string()).collect(),
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+            warnings: Vec::new(),
+        }
+    }
+}
+
+pub const DEFAULT_STATUSES: [&str; 3] = ["Pending", "Ongoing", "Done"];
+
+pub const TODO_FIELDS: [&str; 8] = [
+    "text", "desc", "topic", "priority", "status", "owner", "due", "created",
+];
+
 impl AppConfigs {
     pub fn new() -> Result<Self> {
         let config_file = Self::get_config_path()?;
@@ -21,22 +91,133 @@ impl AppConfigs {
         let config: toml::Value =
             toml::from_str(&config_content).context("Failed to parse config file")?;
 
+        let ai = config.get("AI");
+        let ai_str = |key: &str| ai.and_then(|s| s.get(key)).and_then(|v| v.as_str());
+
+        let privacy = config.get("PRIVACY");
+        let privacy_list = |key: &str| -> Vec<String> {
+            privacy
+                .and_then(|s| s.get(key))
+                .and_then(|v| v.as_array())
+                .map(|values| {
+                    values
+                        .iter()
+                        .filter_map(|v| v.as_str())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+
+        let tui = config.get("TUI");
+        let (keymap, warnings) = load_keymap(tui);
+
+        let ai_u64 = |key: &str, default: u64| {
+            ai.and_then(|s| s.get(key))
+                .and_then(|v| v.as_integer())
+                .map(|v| v.max(1) as u64)
+                .unwrap_or(default)
+        };
+
+        let provider = ai_str("provider").unwrap_or("gemini").to_lowercase();
+        // Configs from before [AI] named the Gemini model in [GEMINI]
+        let legacy_model = config
+            .get("GEMINI")
+            .and_then(|s| s.get("model"))
+            .and_then(|v| v.as_str())
+            .filter(|_| provider == "gemini");
+        // Each provider has its own key, so one is never sent to another provider's server
+        let (key_env, key_file) = match provider.as_str() {
+            "gemini" => ("RUSTYDO_API_KEY".to_string(), "apikey".to_string()),
+            other => (
+                format!("RUSTYDO_{}_API_KEY", other.to_uppercase()),
+                format!("apikey-{}", other),
+            ),
+        };
+
         Ok(Self {
-            model: config["GEMINI"]["model"]
-                .as_str()
-                .context("Missing or invalid model in config")?
-                .to_string(),
+            provider,
+            model: ai_str("model").or(legacy_model).map(str::to_string),
+            embedding_model: ai_str("embedding_model").map(str::to_string),
+            base_url: ai_str("base_url").map(|url| url.trim_end_matches('/').to_string()),
+            timeout_secs: ai_u64("timeout_secs", 60),
+            connect_timeout_secs: ai_u64("connect_timeout_secs", 10),
+            max_retries: ai
+                .and_then(|s| s.get("max_retries"))
+                .and_then(|v| v.as_integer())
+                .map(|v| v.clamp(0, 10) as u32)
+                .unwrap_or(3),
+            context_tokens: ai_u64("context_tokens", 4000).max(200) as usize,
+            cache_ttl_secs: ai
+                .and_then(|s| s.get("cache_ttl_secs"))
+                .and_then(|v| v.as_integer())
+                .map(|v| v.max(0) as u64)
+                .unwrap_or(3600),
+            duplicate_threshold: ai
+                .and_then(|s| s.get("duplicate_threshold"))
+                .and_then(|v| v.as_float().or_else(|| v.as_integer().map(|i| i as f64)))
+                .map(|v| v.clamp(0.0, 1.0) as f32)
+                .unwrap_or(0.0),
+            api_key_env: ai_str("api_key_env").map_or(key_env, str::to_string),
+            api_key_cmd: ai_str("api_key_cmd")
+                .filter(|cmd| !cmd.trim().is_empty())
+                .map(str::to_string),
+            api_key_file: match ai_str("api_key_file") {
+                Some(path) => expand_home(path),
+                None => Self::get_config_dir()?.join(key_file),
+            },
+            standup_template: ai_str("standup_template")
+                .filter(|template| !template.trim().is_empty())
+                .map(str::to_string),
+            privacy: PrivacyConfigs {
+                fields: match privacy.and_then(|s| s.get("fields")) {
+                    Some(_) => privacy_list("fields"),
+                    None => TODO_FIELDS.iter().map(|f| f.to_string()).collect(),
+                },
+                exclude_topics: privacy_list("exclude_topics"),
+                redact: privacy_list("redact"),
+                max_todos: privacy
+                    .and_then(|s| s.get("max_todos"))
+                    .and_then(|v| v.as_integer())
+                    .map(|v| v.max(0) as usize),
+            },
+            tui: TuiConfigs {
+                statuses: tui
+                    .and_then(|s| s.get("statuses"))
+                    .and_then(|v| v.as_array())
+                    .map(|values| {
+                        values
+                            .iter()
+                            .filter_map(|v| v.as_str())
+                            .map(str::to_string)
+                            .collect::<Vec<String>>()
+                    })
+                    .filter(|statuses| !statuses.is_empty())
+                    .unwrap_or_else(|| DEFAULT_STATUSES.iter().map(|s| s.to_string()).collect()),
+                theme: load_theme(tui),
+                keymap,
+                warnings,
+            },
         })
     }
 
-    pub fn get_config_path() -> Result<PathBuf> {
+    pub fn get_config_dir() -> Result<PathBuf> {
         let base_dirs = BaseDirs::new().context("Could not determine config directory")?;
-        let config_dir = base_dirs.config_dir().join("rustydo");
-        Ok(config_dir.join("config.toml"))
+        Ok(base_dirs.config_dir().join("rustydo"))
+    }
+
+    pub fn get_config_path() -> Result<PathBuf> {
+        Ok(Self::get_config_dir()?.join("config.toml"))
     }
 
     pub fn create_default_config() -> Result<()> {
         let config_file = Self::get_config_path()?;
+
+        // Never overwrite the user's settings
+        if config_file.exists() {
+            return Ok(());
+        }
+
         let config_dir = config_file
             .parent()
             .context("Invalid config directory path")?;
@@ -49,10 +230,79 @@ impl AppConfigs {
         }
 
         // Write default config
-        let default_config = r#"
-[GEMINI]
-model = "gemini-pro"  # Changed to Gemini since that's what you're using
-"#;
+        let default_config = r##"
+[AI]
+# gemini, openai (any OpenAI-compatible chat-completions server) or ollama
+provider = "gemini"
+model = "gemini-2.0-flash"
+# base_url = "http://localhost:11434"
+# How long the provider may go without sending anything, streamed answers can take longer in total
+timeout_secs = 60
+connect_timeout_secs = 10
+# Retries with exponential backoff after a rate limit (429) or server error (5xx)
+max_retries = 3
+# Token budget for the todos sent with a prompt. When the list is longer the most
+# relevant todos are sent (open, high priority, due soon, matching the prompt)
+# and the rest are only counted
+context_tokens = 4000
+# The same request over the same todos is answered from a local cache for this
+# long (seconds), 0 turns the cache off
+cache_ttl_secs = 3600
+# Embeddings for `rustydo similar` and the duplicate check when adding a todo
+# embedding_model = "text-embedding-004"
+# Warn when a new todo is at least this similar (0 to 1) to an open one, e.g. 0.9.
+# Off (0) by default, checking embeds every new todo with the provider
+duplicate_threshold = 0
+
+# The API key is looked up in this order:
+#   1. the environment variable named by api_key_env
+#   2. the output of api_key_cmd
+#   3. the contents of api_key_file (must be chmod 600)
+# By default each provider has its own: RUSTYDO_API_KEY and apikey for gemini,
+# RUSTYDO_OPENAI_API_KEY and apikey-openai for openai
+# api_key_env = "RUSTYDO_API_KEY"
+# api_key_cmd = "pass show gemini"
+# api_key_file = "~/.config/rustydo/apikey"
+
+# Prompt used by `rustydo ai standup`. Placeholders: {since}, {today}, {format}, {activity}
+# standup_template = """
+# Write my standup covering {since} to {today} with Done, In progress and Blocked sections.
+# {format}
+#
+# {activity}
+# """
+
+[PRIVACY]
+# Todo fields sent to the AI provider, remove the ones that should stay private
+fields = ["text", "desc", "topic", "priority", "status", "owner", "due", "created"]
+# Todos in these topics are never sent
+exclude_topics = []
+# Regexes replaced with [redacted] in everything sent, e.g. emails and API tokens:
+# redact = ['[\w.+-]+@[\w-]+\.[\w.]+', '(sk|ghp|xox[bp])[-_][A-Za-z0-9_-]{10,}', '(?i)acme corp']
+redact = []
+# Send at most this many todos, open ones first
+# max_todos = 50
+
+[TUI]
+# Columns of the kanban board (Tab in the TUI), left to right
+statuses = ["Pending", "Ongoing", "Done"]
+# purple, light, high-contrast, solarized or basic (16 colours, for terminals without truecolor)
+theme = "purple"
+
+# Change single colours of the theme: a name ("red", "lightblue"), "#rrggbb" or a 0-255 index
+# [TUI.colors]
+# accent = "#ff8800"
+# [TUI.colors.status]
+# Blocked = "red"
+# [TUI.colors.priority]
+# High = "#ff5f87"
+
+# Rebind keys: action = "key" or a list of keys. A key is a character, a name (enter, esc,
+# tab, space, delete, up, pageup, f1...) or a chord like "ctrl-d". ? in the TUI lists the actions
+# [TUI.keys]
+# done = ["d", "ctrl-d"]
+# quit = "ctrl-q"
+"##;
 
         std::fs::write(&config_file, default_config.trim())
             .with_context(|| format!("Failed to write config to {:?}", config_file))?;
@@ -60,3 +310,74 @@ model = "gemini-pro"  # Changed to Gemini since that's what you're using
         Ok(())
     }
 }
+
+// The `[TUI]` theme with the `[TUI.colors]` overrides, colours that don't parse are skipped
+fn load_theme(tui: Option<&toml::Value>) -> Theme {
+    let mut theme = tui
+        .and_then(|s| s.get("theme"))
+        .and_then(|v| v.as_str())
+        .and_then(Theme::builtin)
+        .unwrap_or_default();
+
+    let Some(colors) = tui.and_then(|s| s.get("colors")).and_then(|v| v.as_table()) else {
+        return theme;
+    };
+    for (name, value) in colors {
+        match (name.as_str(), value.as_table()) {
+            ("status", Some(statuses)) => {
+                for (status, value) in statuses {
+                    if let Some(color) = parse_color(value) {
+                        theme.set_status(status, color);
+                    }
+                }
+            }
+            ("priority", Some(priorities)) => {
+                for (priority, value) in priorities {
+                    if let Some(color) = parse_color(value) {
+                        theme.set_priority(priority, color);
+                    }
+                }
+            }
+            _ => {
+                if let Some(color) = parse_color(value) {
+                    theme.set(name, color);
+                }
+            }
+        }
+    }
+    theme
+}
+
+// The `[TUI.keys]` bindings, with a message for every entry that was skipped
+fn load_keymap(tui: Option<&toml::Value>) -> (Keymap, Vec<String>) {
+    let mut keymap = Keymap::default();
+    let mut warnings = Vec::new();
+
+    let Some(keys) = tui.and_then(|s| s.get("keys")).and_then(|v| v.as_table()) else {
+        return (keymap, warnings);
+    };
+    for (action, value) in keys {
+        let bound = match value {
+            toml::Value::String(key) => vec![key.as_str()],
+            toml::Value::Array(keys) => keys.iter().filter_map(|v| v.as_str()).collect(),
+            _ => {
+                warnings.push(format!("Keys for {} must be a string or a list", action));
+                continue;
+            }
+        };
+        if let Err(e) = keymap.bind(action, &bound) {
+            warnings.push(e);
+        }
+    }
+    (keymap, warnings)
+}
+
+// Expand a leading `~/` to the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(base_dirs) = BaseDirs::new()
+    {
+        return base_dirs.home_dir().join(rest);
+    }
+    PathBuf::from(path)
+}
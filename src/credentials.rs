@@ -0,0 +1,154 @@
+use std::error::Error;
+use std::fs;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::configs::AppConfigs;
+use crate::database::DBtodo;
+
+// GET THE API KEY FROM THE ENVIRONMENT, THE KEY COMMAND OR THE KEY FILE
+pub fn get_api_key() -> Result<String, Box<dyn Error>> {
+    let configs = AppConfigs::new()?;
+
+    if let Ok(key) = std::env::var(&configs.api_key_env)
+        && !key.trim().is_empty()
+    {
+        return Ok(key.trim().to_string());
+    }
+
+    if let Some(cmd) = &configs.api_key_cmd {
+        return key_from_command(cmd);
+    }
+
+    if configs.api_key_file.exists() {
+        return key_from_file(&configs.api_key_file);
+    }
+
+    Err(format!(
+        "No API key found. Set {}, configure api_key_cmd in the config, or run `rustydo --apikey-stdin`",
+        configs.api_key_env
+    )
+    .into())
+}
+
+// Run the configured command and use its output as the key
+fn key_from_command(cmd: &str) -> Result<String, Box<dyn Error>> {
+    let output = if cfg!(windows) {
+        Command::new("cmd").args(["/C", cmd]).output()
+    } else {
+        Command::new("sh").args(["-c", cmd]).output()
+    }
+    .map_err(|e| format!("Failed to run api_key_cmd `{}`: {}", cmd, e))?;
+
+    if !output.status.success() {
+        return Err(format!(
+            "api_key_cmd `{}` failed: {}",
+            cmd,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+
+    // Tools like `pass` may print extra lines after the secret
+    let stdout = String::from_utf8(output.stdout)?;
+    let key = stdout.lines().next().unwrap_or("").trim().to_string();
+    if key.is_empty() {
+        return Err(format!("api_key_cmd `{}` printed an empty key", cmd).into());
+    }
+    Ok(key)
+}
+
+fn key_from_file(path: &Path) -> Result<String, Box<dyn Error>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = fs::metadata(path)?.permissions().mode();
+        if mode & 0o077 != 0 {
+            return Err(format!(
+                "Refusing to read {}: permissions are {:o}, run `chmod 600 {}`",
+                path.display(),
+                mode & 0o777,
+                path.display()
+            )
+            .into());
+        }
+    }
+
+    let key = fs::read_to_string(path)?.trim().to_string();
+    if key.is_empty() {
+        return Err(format!("Key file {} is empty", path.display()).into());
+    }
+    Ok(key)
+}
+
+// SAVE THE API KEY TO THE KEY FILE (OWNER READ/WRITE ONLY)
+pub fn store_api_key(key: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let key = key.trim();
+    if key.is_empty() {
+        return Err("The API key is empty".into());
+    }
+
+    let path = AppConfigs::new()?.api_key_file;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(&path)?;
+
+    // The file may have existed before with looser permissions
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+
+    writeln!(file, "{}", key)?;
+    Ok(path)
+}
+
+// READ THE API KEY FROM STDIN SO IT NEVER SHOWS UP IN THE SHELL HISTORY
+pub fn read_key_from_stdin() -> Result<String, Box<dyn Error>> {
+    let stdin = io::stdin();
+    if stdin.is_terminal() {
+        print!("Paste your API key and press Enter: ");
+        io::stdout().flush()?;
+    }
+
+    let mut key = String::new();
+    stdin.lock().read_line(&mut key)?;
+    Ok(key.trim().to_string())
+}
+
+// MOVE A PLAINTEXT KEY LEFT IN todos.db BY OLDER VERSIONS TO THE KEY FILE
+pub fn migrate_plaintext_key() -> Result<(), Box<dyn Error>> {
+    // Older versions only spoke to Gemini, the key stays put until that's the provider again
+    let configs = AppConfigs::new()?;
+    if configs.provider != "gemini" {
+        return Ok(());
+    }
+
+    let db = DBtodo::new()?;
+    let Some(key) = db.take_api_credentials()? else {
+        return Ok(());
+    };
+
+    if configs.api_key_file.exists() {
+        println!("🔐 Removed the plaintext API key from todos.db");
+    } else {
+        let path = store_api_key(&key)?;
+        println!(
+            "🔐 Moved the API key from todos.db to {} (chmod 600)",
+            path.display()
+        );
+    }
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use std::error::Error;
+
 use crate::{arguments::models::Todo, database::DBtodo};
 
 pub fn sample_todos() -> Vec<Todo> {
@@ -6,3 +8,30 @@ pub fn sample_todos() -> Vec<Todo> {
     let todos = db.get_todos().unwrap().iter().cloned().collect();
     todos
 }
+
+/// Notices writes to todos.db from other processes, e.g. `rustydo -a` in another terminal
+#[derive(Debug)]
+pub struct DbWatcher {
+    // Kept open, SQLite's data_version only counts commits from other connections
+    db: DBtodo,
+    version: i64,
+}
+
+impl DbWatcher {
+    pub fn new() -> Result<Self, Box<dyn Error>> {
+        let db = DBtodo::new()?;
+        let version = db.data_version()?;
+        Ok(Self { db, version })
+    }
+
+    // True once for every batch of changes since the last call
+    pub fn changed(&mut self) -> bool {
+        match self.db.data_version() {
+            Ok(version) if version != self.version => {
+                self.version = version;
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -3,12 +3,13 @@ use std::error::Error;
 use directories::BaseDirs;
 use rusqlite::{Connection, Result, params};
 
-use crate::arguments::models::Todo;
+use crate::arguments::models::{AiUsage, ChatMessage, Conversation, Embedding, StatusChange, Todo};
 
 pub struct ConfigDir {
     pub config_dir: String,
 }
 
+#[derive(Debug)]
 pub struct DBtodo {
     pub connection: rusqlite::Connection,
 }
@@ -23,6 +24,45 @@ impl ConfigDir {
     }
 }
 
+const TODO_COLUMNS: &str = "id, name, topic, text, desc, date_added, due, status, owner, parent_id";
+
+fn row_to_todo(row: &rusqlite::Row) -> rusqlite::Result<Todo> {
+    Ok(Todo {
+        id: row.get(0)?,
+        priority: row.get(1)?,
+        topic: row.get(2)?,
+        text: row.get(3)?,
+        desc: row.get(4)?,
+        date_added: row.get(5)?,
+        due: row.get(6)?,
+        status: row.get(7)?,
+        owner: row.get(8)?,
+        parent_id: row.get(9)?,
+    })
+}
+
+// ADD A COLUMN TO AN EXISTING TABLE CREATED BY AN OLDER VERSION
+fn add_column_if_missing(
+    connection: &Connection,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut stmt = connection.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        connection.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition),
+            [],
+        )?;
+    }
+    Ok(())
+}
+
 impl DBtodo {
     pub fn new() -> Result<DBtodo, Box<dyn Error>> {
         let config_dir = ConfigDir::new();
@@ -69,7 +109,79 @@ impl DBtodo {
                 date_added TEXT NOT NULL,
                 due TEXT,
                 status TEXT NOT NULL,
-                owner TEXT NOT NULL
+                owner TEXT NOT NULL,
+                parent_id INTEGER
+            )",
+            [],
+        )?;
+
+        // Columns added after the first release
+        add_column_if_missing(&connection, "todos", "parent_id", "INTEGER")?;
+
+        // AI chat conversations and their messages
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS conversations (
+                id INTEGER PRIMARY KEY,
+                title TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS chat_messages (
+                id INTEGER PRIMARY KEY,
+                conversation_id INTEGER NOT NULL,
+                role TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // Every status a todo went through, used by the standup summary
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS status_history (
+                id INTEGER PRIMARY KEY,
+                todo_id INTEGER NOT NULL,
+                from_status TEXT,
+                to_status TEXT NOT NULL,
+                changed_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        // AI answers by a SHA-256 of the request that produced them, and what every call cost
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS ai_cache (
+                request TEXT PRIMARY KEY,
+                response TEXT NOT NULL,
+                created_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS ai_usage (
+                id INTEGER PRIMARY KEY,
+                called_at TEXT NOT NULL,
+                model TEXT NOT NULL,
+                prompt_tokens INTEGER,
+                completion_tokens INTEGER,
+                cached INTEGER NOT NULL DEFAULT 0
+            )",
+            [],
+        )?;
+
+        // One vector per todo and embedding model, for `similar` and the duplicate check
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS embeddings (
+                todo_id INTEGER NOT NULL,
+                model TEXT NOT NULL,
+                source TEXT NOT NULL,
+                vector BLOB NOT NULL,
+                PRIMARY KEY (todo_id, model)
             )",
             [],
         )?;
@@ -77,11 +189,34 @@ impl DBtodo {
         Ok(DBtodo { connection })
     }
 
-    /// Adds a new todo to the database (better than standalone function)
-    pub fn add_todo(&self, todo: &Todo) -> Result<(), Box<dyn Error>> {
+    // REMEMBER A STATUS CHANGE, `from` IS NONE WHEN THE TODO WAS CREATED
+    fn record_status_change(
+        &self,
+        todo_id: i64,
+        from: Option<&str>,
+        to: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         self.connection.execute(
-            "INSERT INTO todos (name, topic, text, desc, date_added, due, status, owner) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            "INSERT INTO status_history (todo_id, from_status, to_status, changed_at) VALUES (?1, ?2, ?3, ?4)",
+            params![todo_id, from, to, now],
+        )?;
+        Ok(())
+    }
+
+    fn current_status(&self, id: i64) -> Result<Option<String>, Box<dyn Error>> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT status FROM todos WHERE id = ?")?;
+        let mut rows = stmt.query_map(params![id], |row| row.get::<_, String>(0))?;
+        Ok(rows.next().transpose()?)
+    }
+
+    /// Adds a new todo to the database (better than standalone function), returns its id
+    pub fn add_todo(&self, todo: &Todo) -> Result<i64, Box<dyn Error>> {
+        self.connection.execute(
+            "INSERT INTO todos (name, topic, text, desc, date_added, due, status, owner, parent_id) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
             params![
                 &todo.priority,
                 &todo.topic,
@@ -90,44 +225,114 @@ impl DBtodo {
                 &todo.date_added,
                 &todo.due,
                 &todo.status,
-                &todo.owner
+                &todo.owner,
+                &todo.parent_id.map(|id| id as i64)
             ],
         )?;
         // println!("✅ Todo added successfully!");
+        let id = self.connection.last_insert_rowid();
+        self.record_status_change(id, None, &todo.status)?;
+        Ok(id)
+    }
+
+    // EDIT ALL THE USER FACING FIELDS OF A TODO
+    pub fn edit_todo(&self, todo: &Todo) -> Result<(), Box<dyn Error>> {
+        let before = self.current_status(todo.id as i64)?;
+        let changes = self.connection.execute(
+            "UPDATE todos SET name = ?1, topic = ?2, text = ?3, desc = ?4, due = ?5, status = ?6, owner = ?7
+             WHERE id = ?8",
+            params![
+                &todo.priority,
+                &todo.topic,
+                &todo.text,
+                &todo.desc,
+                &todo.due,
+                &todo.status,
+                &todo.owner,
+                todo.id as i64
+            ],
+        )?;
+
+        if changes == 0 {
+            return Err(format!("No todo found with id: {}", todo.id).into());
+        }
+        if before.as_deref() != Some(todo.status.as_str()) {
+            self.record_status_change(todo.id as i64, before.as_deref(), &todo.status)?;
+        }
         Ok(())
     }
 
-    // DELETE TODO BASED ON ID
-    pub fn delete_todo(&self, id: i32) -> Result<(), Box<dyn Error>> {
-        let changes = self
+    // CHANGES WHENEVER ANOTHER CONNECTION COMMITS TO THE DATABASE
+    pub fn data_version(&self) -> Result<i64, Box<dyn Error>> {
+        Ok(self
             .connection
-            .execute("DELETE FROM todos WHERE id = ?", params![id])?;
+            .query_row("PRAGMA data_version", params![], |row| row.get(0))?)
+    }
 
-        if changes > 0 {
-            println!("✅ Todo deleted successfully!");
-        } else {
-            println!("❌ No todo found with id: {}", id);
+    // ADD SEVERAL TODOS IN ONE TRANSACTION, ALL OR NOTHING
+    pub fn add_todos(&self, todos: &[Todo]) -> Result<(), Box<dyn Error>> {
+        let transaction = self.connection.unchecked_transaction()?;
+        for todo in todos {
+            self.add_todo(todo)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    // EDIT SEVERAL TODOS IN ONE TRANSACTION, ALL OR NOTHING
+    pub fn edit_todos(&self, todos: &[Todo]) -> Result<(), Box<dyn Error>> {
+        let transaction = self.connection.unchecked_transaction()?;
+        for todo in todos {
+            self.edit_todo(todo)?;
         }
+        transaction.commit()?;
+        Ok(())
+    }
 
+    // DELETE SEVERAL TODOS IN ONE TRANSACTION, ALL OR NOTHING
+    pub fn delete_todos(&self, ids: &[usize]) -> Result<(), Box<dyn Error>> {
+        let transaction = self.connection.unchecked_transaction()?;
+        for id in ids {
+            self.connection
+                .execute("DELETE FROM todos WHERE id = ?", params![*id as i64])?;
+            self.connection.execute(
+                "DELETE FROM embeddings WHERE todo_id = ?",
+                params![*id as i64],
+            )?;
+            // Subtasks of a deleted todo become top-level todos
+            self.connection.execute(
+                "UPDATE todos SET parent_id = NULL WHERE parent_id = ?",
+                params![*id as i64],
+            )?;
+        }
+        transaction.commit()?;
         Ok(())
     }
 
+    // DELETE TODO BASED ON ID, FALSE WHEN THERE WAS NO SUCH TODO
+    pub fn delete_todo(&self, id: i32) -> Result<bool, Box<dyn Error>> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let changes = self
+            .connection
+            .execute("DELETE FROM todos WHERE id = ?", params![id])?;
+        self.connection
+            .execute("DELETE FROM embeddings WHERE todo_id = ?", params![id])?;
+        // Subtasks of a deleted todo become top-level todos
+        self.connection.execute(
+            "UPDATE todos SET parent_id = NULL WHERE parent_id = ?",
+            params![id],
+        )?;
+        transaction.commit()?;
+
+        Ok(changes > 0)
+    }
+
     // SHOW ALL THE TODOS
     pub fn get_todos(&self) -> Result<Vec<Todo>, Box<dyn Error>> {
-        let mut stmt = self.connection.prepare("SELECT * FROM todos")?;
-        let todos_iter = stmt.query_map(params![], |row| {
-            Ok(Todo {
-                id: row.get(0)?,
-                priority: row.get(1)?,
-                topic: row.get(2)?,
-                text: row.get(3)?,
-                desc: row.get(4)?,
-                date_added: row.get(5)?,
-                due: row.get(6)?,
-                status: row.get(7)?,
-                owner: row.get(8)?,
-            })
-        })?;
+        let mut stmt = self
+            .connection
+            .prepare(&format!("SELECT {} FROM todos", TODO_COLUMNS))?;
+        let todos_iter = stmt.query_map(params![], row_to_todo)?;
 
         let mut todos: Vec<Todo> = Vec::new();
         for todo in todos_iter {
@@ -136,23 +341,37 @@ impl DBtodo {
         Ok(todos)
     }
 
-    // UPDATE TODO STATUS
-    pub fn update_todo(&self, id: i32, status: Option<String>) -> Result<(), Box<dyn Error>> {
+    // GET A SINGLE TODO BY ID
+    pub fn get_todo(&self, id: i32) -> Result<Option<Todo>, Box<dyn Error>> {
+        let mut stmt = self
+            .connection
+            .prepare(&format!("SELECT {} FROM todos WHERE id = ?", TODO_COLUMNS))?;
+        let mut rows = stmt.query_map(params![id], row_to_todo)?;
+
+        Ok(rows.next().transpose()?)
+    }
+
+    // UPDATE TODO STATUS, FALSE WHEN THERE WAS NO SUCH TODO
+    pub fn update_todo(&self, id: i32, status: Option<String>) -> Result<bool, Box<dyn Error>> {
+        let before = self.current_status(id as i64)?;
         let changes = self.connection.execute(
             "UPDATE todos SET status = ? WHERE id = ?",
             params![status, id],
         )?;
-        if changes > 0 {
-            return Ok(());
-        } else {
-            println!("❌ No todo found with id: {}", id);
+        if changes > 0
+            && let Some(status) = &status
+            && before.as_ref() != Some(status)
+        {
+            self.record_status_change(id as i64, before.as_deref(), status)?;
         }
-        Ok(())
+        Ok(changes > 0)
     }
 
     // CLEAR ALL TODOS FROM DB
     pub fn clear_all_todos(&self) -> Result<(), Box<dyn Error>> {
         let changes = self.connection.execute("DELETE FROM todos", params![])?;
+        self.connection
+            .execute("DELETE FROM embeddings", params![])?;
         if changes > 0 {
             println!("✅ All todos cleared successfully!");
         } else {
@@ -163,6 +382,8 @@ impl DBtodo {
 
     pub fn flush_db(&self) -> Result<(), Box<dyn Error>> {
         let changes = self.connection.execute("DELETE FROM todos", params![])?;
+        self.connection
+            .execute("DELETE FROM embeddings", params![])?;
         if changes > 0 {
             println!("");
             println!("✅ All todos cleared successfully!");
@@ -175,30 +396,230 @@ impl DBtodo {
         Ok(())
     }
 
-    // SET THE API KEY CREDENTRIALS
-    pub fn set_api_credentials(&self, apikey: Option<String>) -> Result<(), Box<dyn Error>> {
-        // Always clear the table first
+    // TAKE (READ AND DELETE) A PLAINTEXT API KEY STORED BY OLDER VERSIONS
+    pub fn take_api_credentials(&self) -> Result<Option<String>, Box<dyn Error>> {
+        let mut stmt = self.connection.prepare("SELECT apikey FROM model")?;
+        let keys = stmt
+            .query_map(params![], |row| row.get::<_, String>(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+
+        if keys.is_empty() {
+            return Ok(None);
+        }
+
         self.connection.execute("DELETE FROM model", [])?;
+        // Rewrite the file so the old key doesn't linger in free pages
+        self.connection.execute_batch("VACUUM")?;
 
-        // Insert the new API key
-        let changes = self.connection.execute(
-            "INSERT INTO model (name, apikey) VALUES (?, ?)",
-            params!["gemini", apikey.as_deref()],
+        Ok(keys.into_iter().find(|key| !key.trim().is_empty()))
+    }
+
+    // START A NEW CHAT CONVERSATION
+    pub fn create_conversation(&self, title: &str) -> Result<i64, Box<dyn Error>> {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        self.connection.execute(
+            "INSERT INTO conversations (title, created_at, updated_at) VALUES (?1, ?2, ?2)",
+            params![title, now],
         )?;
+        Ok(self.connection.last_insert_rowid())
+    }
 
-        if changes > 0 {
-            println!("✅ API credentials set successfully!");
-        } else {
-            println!("❌ Failed to set API credentials.");
+    // APPEND A MESSAGE TO A CONVERSATION
+    pub fn add_chat_message(
+        &self,
+        conversation_id: i64,
+        role: &str,
+        content: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M").to_string();
+        self.connection.execute(
+            "INSERT INTO chat_messages (conversation_id, role, content, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![conversation_id, role, content, now],
+        )?;
+        self.connection.execute(
+            "UPDATE conversations SET updated_at = ?1 WHERE id = ?2",
+            params![now, conversation_id],
+        )?;
+        Ok(())
+    }
+
+    // ALL THE SAVED CONVERSATIONS, MOST RECENT FIRST
+    pub fn get_conversations(&self) -> Result<Vec<Conversation>, Box<dyn Error>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT c.id, c.title, c.updated_at, COUNT(m.id)
+             FROM conversations c LEFT JOIN chat_messages m ON m.conversation_id = c.id
+             GROUP BY c.id ORDER BY c.updated_at DESC, c.id DESC",
+        )?;
+        let conversations = stmt
+            .query_map([], |row| {
+                Ok(Conversation {
+                    id: row.get(0)?,
+                    title: row.get(1)?,
+                    updated_at: row.get(2)?,
+                    messages: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(conversations)
+    }
+
+    // THE MESSAGES OF A CONVERSATION, OLDEST FIRST
+    pub fn get_chat_messages(
+        &self,
+        conversation_id: i64,
+    ) -> Result<Option<Vec<ChatMessage>>, Box<dyn Error>> {
+        let exists: i64 = self.connection.query_row(
+            "SELECT COUNT(*) FROM conversations WHERE id = ?",
+            params![conversation_id],
+            |row| row.get(0),
+        )?;
+        if exists == 0 {
+            return Ok(None);
         }
 
+        let mut stmt = self.connection.prepare(
+            "SELECT role, content FROM chat_messages WHERE conversation_id = ? ORDER BY id",
+        )?;
+        let messages = stmt
+            .query_map(params![conversation_id], |row| {
+                Ok(ChatMessage {
+                    role: row.get(0)?,
+                    content: row.get(1)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(messages))
+    }
+
+    // STATUS CHANGES SINCE A "%Y-%m-%d %H:%M:%S" TIMESTAMP, OLDEST FIRST
+    pub fn get_status_changes(&self, since: &str) -> Result<Vec<StatusChange>, Box<dyn Error>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT h.todo_id, t.text, h.from_status, h.to_status, h.changed_at
+             FROM status_history h JOIN todos t ON t.id = h.todo_id
+             WHERE h.changed_at >= ? ORDER BY h.changed_at, h.id",
+        )?;
+        let changes = stmt
+            .query_map(params![since], |row| {
+                Ok(StatusChange {
+                    todo_id: row.get(0)?,
+                    text: row.get(1)?,
+                    from: row.get(2)?,
+                    to: row.get(3)?,
+                    changed_at: row.get(4)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(changes)
+    }
+
+    // A CACHED AI ANSWER STORED AFTER `since` ("%Y-%m-%d %H:%M:%S")
+    pub fn get_cached_response(
+        &self,
+        request: &str,
+        since: &str,
+    ) -> Result<Option<String>, Box<dyn Error>> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT response FROM ai_cache WHERE request = ?1 AND created_at >= ?2")?;
+        let mut rows = stmt.query_map(params![request, since], |row| row.get::<_, String>(0))?;
+        Ok(rows.next().transpose()?)
+    }
+
+    // CACHE AN AI ANSWER, DROPPING THE ONES STORED BEFORE `expired_before`
+    pub fn cache_response(
+        &self,
+        request: &str,
+        response: &str,
+        expired_before: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.connection.execute(
+            "DELETE FROM ai_cache WHERE created_at < ?",
+            params![expired_before],
+        )?;
+        self.connection.execute(
+            "INSERT OR REPLACE INTO ai_cache (request, response, created_at) VALUES (?1, ?2, ?3)",
+            params![request, response, now],
+        )?;
         Ok(())
     }
 
-    // GET THE API KEY CREDENTRIALS
-    pub fn get_api_credentials(&self) -> Result<String, Box<dyn Error>> {
-        let mut stmt = self.connection.prepare("SELECT apikey FROM model")?;
-        let apikey = stmt.query_row(params![], |row| row.get(0))?;
-        Ok(apikey)
+    // RECORD ONE AI CALL, THE TOKENS ARE NONE WHEN THE PROVIDER DIDN'T REPORT THEM
+    pub fn record_ai_usage(
+        &self,
+        model: &str,
+        tokens: Option<(u64, u64)>,
+        cached: bool,
+    ) -> Result<(), Box<dyn Error>> {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        self.connection.execute(
+            "INSERT INTO ai_usage (called_at, model, prompt_tokens, completion_tokens, cached) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                now,
+                model,
+                tokens.map(|(prompt, _)| prompt as i64),
+                tokens.map(|(_, completion)| completion as i64),
+                cached
+            ],
+        )?;
+        Ok(())
+    }
+
+    // AI CALLS AND TOKENS PER DAY AND MODEL SINCE A "%Y-%m-%d" DATE, MOST RECENT FIRST
+    pub fn get_ai_usage(&self, since: &str) -> Result<Vec<AiUsage>, Box<dyn Error>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT date(called_at), model, COUNT(*), SUM(cached),
+                    COALESCE(SUM(prompt_tokens), 0), COALESCE(SUM(completion_tokens), 0)
+             FROM ai_usage WHERE date(called_at) >= ?
+             GROUP BY date(called_at), model ORDER BY date(called_at) DESC, model",
+        )?;
+        let usage = stmt
+            .query_map(params![since], |row| {
+                Ok(AiUsage {
+                    day: row.get(0)?,
+                    model: row.get(1)?,
+                    calls: row.get::<_, i64>(2)? as u64,
+                    cached: row.get::<_, i64>(3)? as u64,
+                    prompt_tokens: row.get::<_, i64>(4)? as u64,
+                    completion_tokens: row.get::<_, i64>(5)? as u64,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(usage)
+    }
+
+    // THE STORED EMBEDDINGS OF ONE MODEL
+    pub fn get_embeddings(&self, model: &str) -> Result<Vec<Embedding>, Box<dyn Error>> {
+        let mut stmt = self
+            .connection
+            .prepare("SELECT todo_id, source, vector FROM embeddings WHERE model = ?")?;
+        let embeddings = stmt
+            .query_map(params![model], |row| {
+                let bytes: Vec<u8> = row.get(2)?;
+                Ok(Embedding {
+                    todo_id: row.get(0)?,
+                    source: row.get(1)?,
+                    vector: bytes
+                        .chunks_exact(4)
+                        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                        .collect(),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(embeddings)
+    }
+
+    // STORE (OR REPLACE) A TODO'S EMBEDDING
+    pub fn save_embedding(&self, model: &str, embedding: &Embedding) -> Result<(), Box<dyn Error>> {
+        let bytes = embedding
+            .vector
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect::<Vec<u8>>();
+        self.connection.execute(
+            "INSERT OR REPLACE INTO embeddings (todo_id, model, source, vector) VALUES (?1, ?2, ?3, ?4)",
+            params![embedding.todo_id as i64, model, embedding.source, bytes],
+        )?;
+        Ok(())
     }
 }
@@ -0,0 +1,89 @@
+use crossterm::event::{self, Event, KeyEvent};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+
+use crate::ai::panel::PanelEvent;
+use crate::data::DbWatcher;
+
+// How often timers in the TUI advance, e.g. status messages expiring
+const TICK_RATE: Duration = Duration::from_millis(250);
+// How often the TUI looks for changes made by other processes
+const WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Everything the TUI reacts to
+#[derive(Debug)]
+pub enum AppEvent {
+    Key(KeyEvent),
+    Resize,
+    Tick,
+    /// Another connection wrote to the database
+    DbChanged,
+    /// Part of a streamed AI answer, with the number of the request it belongs to
+    Ai(u64, PanelEvent),
+}
+
+pub type EventSender = UnboundedSender<AppEvent>;
+
+/// One channel fed by the terminal, the tick timer, the database watcher and background tasks
+#[derive(Debug)]
+pub struct Events {
+    sender: EventSender,
+    receiver: UnboundedReceiver<AppEvent>,
+}
+
+impl Events {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+
+        // crossterm's read blocks, so the terminal gets its own thread
+        let terminal = sender.clone();
+        std::thread::spawn(move || {
+            while let Ok(event) = event::read() {
+                let event = match event {
+                    Event::Key(key) => AppEvent::Key(key),
+                    Event::Resize(_, _) => AppEvent::Resize,
+                    _ => continue,
+                };
+                if terminal.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let ticks = sender.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(TICK_RATE);
+            loop {
+                interval.tick().await;
+                if ticks.send(AppEvent::Tick).is_err() {
+                    break;
+                }
+            }
+        });
+
+        // Without a database to watch the TUI still works, it just won't reload by itself
+        if let Ok(mut watcher) = DbWatcher::new() {
+            let changes = sender.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(WATCH_INTERVAL);
+                loop {
+                    interval.tick().await;
+                    if watcher.changed() && changes.send(AppEvent::DbChanged).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+
+        Self { sender, receiver }
+    }
+
+    // For background tasks that report back to the TUI
+    pub fn sender(&self) -> EventSender {
+        self.sender.clone()
+    }
+
+    pub async fn next(&mut self) -> Option<AppEvent> {
+        self.receiver.recv().await
+    }
+}
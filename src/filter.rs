@@ -0,0 +1,104 @@
+use crate::arguments::models::Todo;
+
+/// The TUI filter bar: plain words plus `status:`, `owner:`, `topic:` and `priority:` qualifiers
+#[derive(Debug, Default)]
+pub struct Filter {
+    pub query: String,
+    // Typing in the bar, the filter stays applied once it's closed
+    pub open: bool,
+}
+
+impl Filter {
+    pub fn is_active(&self) -> bool {
+        !self.query.trim().is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.query.clear();
+        self.open = false;
+    }
+
+    // Every term has to match, qualifiers compare the start of the field so `status:on` works
+    pub fn matches(&self, todo: &Todo) -> bool {
+        self.query.split_whitespace().all(|term| {
+            let term = term.to_lowercase();
+            let field = |value: &str, prefix: &str| value.to_lowercase().starts_with(prefix);
+            match term.split_once(':') {
+                Some(("status", value)) => field(&todo.status, value),
+                Some(("owner", value)) => field(&todo.owner, value),
+                Some(("topic", value)) => field(&todo.topic, value),
+                Some(("priority", value)) => field(&todo.priority, value),
+                _ => format!("{} {} {} {}", todo.text, todo.desc, todo.topic, todo.owner)
+                    .to_lowercase()
+                    .contains(&term),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo() -> Todo {
+        Todo {
+            id: 1,
+            priority: "High".to_string(),
+            topic: "Ops".to_string(),
+            text: "Renew the TLS cert".to_string(),
+            desc: "Expires at the end of the month".to_string(),
+            date_added: "01-10-26".to_string(),
+            status: "Ongoing".to_string(),
+            owner: "Alex".to_string(),
+            due: "2026-10-30".to_string(),
+            parent_id: None,
+        }
+    }
+
+    fn matches(query: &str) -> bool {
+        Filter {
+            query: query.to_string(),
+            open: false,
+        }
+        .matches(&todo())
+    }
+
+    #[test]
+    fn an_empty_filter_matches_everything() {
+        assert!(matches(""));
+        assert!(matches("   "));
+    }
+
+    #[test]
+    fn words_match_text_description_topic_and_owner() {
+        assert!(matches("tls"));
+        assert!(matches("MONTH"));
+        assert!(matches("ops"));
+        assert!(matches("alex"));
+        assert!(!matches("deploy"));
+        // Status and priority are only matched through their qualifiers
+        assert!(!matches("ongoing"));
+    }
+
+    #[test]
+    fn qualifiers_match_the_start_of_their_field() {
+        assert!(matches("status:on"));
+        assert!(matches("Owner:AL"));
+        assert!(matches("topic:ops"));
+        assert!(matches("priority:h"));
+        assert!(!matches("status:going"));
+        assert!(!matches("owner:sam"));
+    }
+
+    #[test]
+    fn every_term_has_to_match() {
+        assert!(matches("status:ongoing owner:alex cert"));
+        assert!(!matches("status:ongoing owner:sam"));
+        assert!(!matches("cert deploy"));
+    }
+
+    #[test]
+    fn unknown_qualifiers_are_plain_words() {
+        assert!(!matches("due:2026"));
+    }
+}
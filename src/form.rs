@@ -0,0 +1,243 @@
+use chrono::NaiveDate;
+use crossterm::event::{KeyCode, KeyEvent};
+use std::error::Error;
+
+use crate::arguments::{add_todo::build_todo, models::Todo};
+use crate::database::DBtodo;
+
+pub const PRIORITIES: [&str; 4] = ["Low", "Normal", "Medium", "High"];
+
+const DEFAULT_DESC: &str = "No description provided";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Text,
+    Desc,
+    Topic,
+    Priority,
+    Owner,
+    Due,
+}
+
+impl Field {
+    pub const ALL: [Field; 6] = [
+        Field::Text,
+        Field::Desc,
+        Field::Topic,
+        Field::Priority,
+        Field::Owner,
+        Field::Due,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::Text => "Todo",
+            Field::Desc => "Description",
+            Field::Topic => "Topic",
+            Field::Priority => "Priority",
+            Field::Owner => "Owner",
+            Field::Due => "Due date",
+        }
+    }
+
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            Field::Text => "What needs doing",
+            Field::Desc => "Optional",
+            Field::Topic => "General",
+            Field::Priority => "",
+            Field::Owner => "You",
+            Field::Due => "YYYY-MM-DD, empty for none",
+        }
+    }
+}
+
+/// What a key press in the form asks the TUI to do
+pub enum FormAction {
+    Continue,
+    Cancel,
+    /// Saved under this id
+    Saved(usize),
+}
+
+/// State of the add/edit todo form in the TUI
+#[derive(Debug)]
+pub struct TodoForm {
+    pub focus: Field,
+    pub text: String,
+    pub desc: String,
+    pub topic: String,
+    pub priority: usize,
+    pub owner: String,
+    pub due: String,
+    pub error: Option<String>,
+    // The todo being edited, None when adding
+    editing: Option<Todo>,
+}
+
+impl TodoForm {
+    pub fn add() -> Self {
+        Self {
+            focus: Field::Text,
+            text: String::new(),
+            desc: String::new(),
+            topic: String::new(),
+            priority: 1, // Normal
+            owner: String::new(),
+            due: String::new(),
+            error: None,
+            editing: None,
+        }
+    }
+
+    pub fn edit(todo: &Todo) -> Self {
+        Self {
+            focus: Field::Text,
+            text: todo.text.clone(),
+            desc: if todo.desc == DEFAULT_DESC {
+                String::new()
+            } else {
+                todo.desc.clone()
+            },
+            topic: todo.topic.clone(),
+            priority: PRIORITIES
+                .iter()
+                .position(|p| p.eq_ignore_ascii_case(&todo.priority))
+                .unwrap_or(1),
+            owner: todo.owner.clone(),
+            due: if todo.due == "-" {
+                String::new()
+            } else {
+                todo.due.clone()
+            },
+            error: None,
+            editing: Some(todo.clone()),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        match &self.editing {
+            Some(todo) => format!(" EDIT TODO #{} ", todo.id),
+            None => " NEW TODO ".to_string(),
+        }
+    }
+
+    pub fn value(&self, field: Field) -> &str {
+        match field {
+            Field::Text => &self.text,
+            Field::Desc => &self.desc,
+            Field::Topic => &self.topic,
+            Field::Priority => PRIORITIES[self.priority],
+            Field::Owner => &self.owner,
+            Field::Due => &self.due,
+        }
+    }
+
+    fn input(&mut self) -> Option<&mut String> {
+        match self.focus {
+            Field::Text => Some(&mut self.text),
+            Field::Desc => Some(&mut self.desc),
+            Field::Topic => Some(&mut self.topic),
+            Field::Priority => None,
+            Field::Owner => Some(&mut self.owner),
+            Field::Due => Some(&mut self.due),
+        }
+    }
+
+    fn move_focus(&mut self, step: isize) {
+        let index = Field::ALL
+            .iter()
+            .position(|f| *f == self.focus)
+            .unwrap_or(0) as isize;
+        let count = Field::ALL.len() as isize;
+        self.focus = Field::ALL[(index + step).rem_euclid(count) as usize];
+    }
+
+    fn cycle_priority(&mut self, step: isize) {
+        let count = PRIORITIES.len() as isize;
+        self.priority = (self.priority as isize + step).rem_euclid(count) as usize;
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> FormAction {
+        match key.code {
+            KeyCode::Esc => return FormAction::Cancel,
+            KeyCode::Enter => match self.save() {
+                Ok(id) => return FormAction::Saved(id),
+                Err(e) => self.error = Some(e.to_string()),
+            },
+            KeyCode::Tab | KeyCode::Down => self.move_focus(1),
+            KeyCode::BackTab | KeyCode::Up => self.move_focus(-1),
+            KeyCode::Left if self.focus == Field::Priority => self.cycle_priority(-1),
+            KeyCode::Right | KeyCode::Char(' ') if self.focus == Field::Priority => {
+                self.cycle_priority(1)
+            }
+            // l/m/n/h pick a priority directly
+            KeyCode::Char(c) if self.focus == Field::Priority => {
+                if let Some(index) = PRIORITIES
+                    .iter()
+                    .position(|p| p.starts_with(c.to_ascii_uppercase()))
+                {
+                    self.priority = index;
+                }
+            }
+            KeyCode::Backspace => {
+                if let Some(input) = self.input() {
+                    input.pop();
+                }
+            }
+            KeyCode::Char(c) => {
+                if let Some(input) = self.input() {
+                    input.push(c);
+                }
+            }
+            _ => {}
+        }
+        FormAction::Continue
+    }
+
+    // Check the fields, moving the focus to the first one that is wrong
+    fn validate(&mut self) -> Result<Todo, Box<dyn Error>> {
+        if self.text.trim().is_empty() {
+            self.focus = Field::Text;
+            return Err("The todo text can't be empty.".into());
+        }
+        let due = self.due.trim();
+        if !due.is_empty() && NaiveDate::parse_from_str(due, "%Y-%m-%d").is_err() {
+            self.focus = Field::Due;
+            return Err(format!("'{}' isn't a date, use YYYY-MM-DD.", due).into());
+        }
+
+        let optional = |value: &str| {
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        build_todo(
+            self.text.clone(),
+            optional(&self.topic),
+            Some(PRIORITIES[self.priority].to_string()),
+            optional(&self.owner),
+            optional(&self.due),
+            optional(&self.desc),
+        )
+    }
+
+    // Add the new todo or update the edited one, returns its id
+    fn save(&mut self) -> Result<usize, Box<dyn Error>> {
+        let todo = self.validate()?;
+        let db = DBtodo::new()?;
+        match &self.editing {
+            Some(existing) => {
+                let edited = Todo {
+                    id: existing.id,
+                    date_added: existing.date_added.clone(),
+                    status: existing.status.clone(),
+                    parent_id: existing.parent_id,
+                    ..todo
+                };
+                db.edit_todo(&edited)?;
+                Ok(existing.id)
+            }
+            None => Ok(db.add_todo(&todo)? as usize),
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use chrono::Local;
+use std::collections::HashSet;
+
+use crate::arguments::models::Todo;
+use crate::calendar::due_date;
+
+const PRIORITY_ORDER: [&str; 4] = ["High", "Medium", "Normal", "Low"];
+const DUE_ORDER: [&str; 6] = [
+    "Overdue",
+    "Today",
+    "This week",
+    "Later",
+    "Earlier",
+    "No due date",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupBy {
+    None,
+    Topic,
+    Owner,
+    Priority,
+    Status,
+    Due,
+}
+
+impl GroupBy {
+    // The order `g` cycles through
+    pub fn next(self) -> Self {
+        match self {
+            GroupBy::None => GroupBy::Topic,
+            GroupBy::Topic => GroupBy::Owner,
+            GroupBy::Owner => GroupBy::Priority,
+            GroupBy::Priority => GroupBy::Status,
+            GroupBy::Status => GroupBy::Due,
+            GroupBy::Due => GroupBy::None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GroupBy::None => "None",
+            GroupBy::Topic => "Topic",
+            GroupBy::Owner => "Owner",
+            GroupBy::Priority => "Priority",
+            GroupBy::Status => "Status",
+            GroupBy::Due => "Due",
+        }
+    }
+
+    /// The group a todo falls in
+    pub fn key(&self, todo: &Todo) -> String {
+        match self {
+            GroupBy::None => String::new(),
+            GroupBy::Topic => todo.topic.clone(),
+            GroupBy::Owner => todo.owner.clone(),
+            GroupBy::Priority => todo.priority.clone(),
+            GroupBy::Status => todo.status.clone(),
+            GroupBy::Due => due_bucket(todo).to_string(),
+        }
+    }
+}
+
+/// A line of the grouped table
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableRow {
+    Group {
+        name: String,
+        count: usize,
+        done: usize,
+        collapsed: bool,
+    },
+    /// Index in `App.todos`
+    Todo(usize),
+}
+
+fn due_bucket(todo: &Todo) -> &'static str {
+    let Some(due) = due_date(todo) else {
+        return "No due date";
+    };
+    match (due - Local::now().date_naive()).num_days() {
+        ..0 if todo.status == "Done" => "Earlier",
+        ..0 => "Overdue",
+        0 => "Today",
+        1..=7 => "This week",
+        _ => "Later",
+    }
+}
+
+// Where a group goes: fixed orders for priority, status and due, alphabetical otherwise
+fn rank(group_by: GroupBy, statuses: &[String], name: &str) -> (usize, String) {
+    let position = match group_by {
+        GroupBy::Priority => PRIORITY_ORDER.iter().position(|p| *p == name),
+        GroupBy::Status => statuses.iter().position(|s| s.eq_ignore_ascii_case(name)),
+        GroupBy::Due => DUE_ORDER.iter().position(|d| *d == name),
+        _ => None,
+    };
+    (position.unwrap_or(usize::MAX), name.to_lowercase())
+}
+
+/// Group headers, each followed by its todos unless the group is collapsed
+pub fn rows(
+    todos: &[Todo],
+    group_by: GroupBy,
+    collapsed: &HashSet<String>,
+    statuses: &[String],
+) -> Vec<TableRow> {
+    if group_by == GroupBy::None {
+        return (0..todos.len()).map(TableRow::Todo).collect();
+    }
+
+    let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+    for (index, todo) in todos.iter().enumerate() {
+        let key = group_by.key(todo);
+        match groups.iter_mut().find(|(name, _)| *name == key) {
+            Some((_, members)) => members.push(index),
+            None => groups.push((key, vec![index])),
+        }
+    }
+    groups.sort_by_key(|(name, _)| rank(group_by, statuses, name));
+
+    let mut rows = Vec::new();
+    for (name, members) in groups {
+        let is_collapsed = collapsed.contains(&name);
+        rows.push(TableRow::Group {
+            name: name.clone(),
+            count: members.len(),
+            done: members
+                .iter()
+                .filter(|index| todos[**index].status == "Done")
+                .count(),
+            collapsed: is_collapsed,
+        });
+        if !is_collapsed {
+            rows.extend(members.into_iter().map(TableRow::Todo));
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(topic: &str, priority: &str, status: &str, due: &str) -> Todo {
+        Todo {
+            id: 0,
+            priority: priority.to_string(),
+            topic: topic.to_string(),
+            text: String::new(),
+            desc: String::new(),
+            date_added: String::new(),
+            status: status.to_string(),
+            owner: "You".to_string(),
+            due: due.to_string(),
+            parent_id: None,
+        }
+    }
+
+    fn statuses() -> Vec<String> {
+        ["Pending", "Ongoing", "Done"].map(str::to_string).to_vec()
+    }
+
+    fn group(name: &str, count: usize, done: usize, collapsed: bool) -> TableRow {
+        TableRow::Group {
+            name: name.to_string(),
+            count,
+            done,
+            collapsed,
+        }
+    }
+
+    #[test]
+    fn without_grouping_every_todo_is_a_row() {
+        let todos = vec![
+            todo("Ops", "Low", "Pending", "-"),
+            todo("Docs", "Low", "Pending", "-"),
+        ];
+        assert_eq!(
+            rows(&todos, GroupBy::None, &HashSet::new(), &statuses()),
+            [TableRow::Todo(0), TableRow::Todo(1)]
+        );
+    }
+
+    #[test]
+    fn topics_are_sorted_alphabetically_with_done_counts() {
+        let todos = vec![
+            todo("Ops", "Low", "Done", "-"),
+            todo("docs", "Low", "Pending", "-"),
+            todo("Ops", "Low", "Pending", "-"),
+        ];
+        assert_eq!(
+            rows(&todos, GroupBy::Topic, &HashSet::new(), &statuses()),
+            [
+                group("docs", 1, 0, false),
+                TableRow::Todo(1),
+                group("Ops", 2, 1, false),
+                TableRow::Todo(0),
+                TableRow::Todo(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn priorities_and_statuses_keep_their_own_order() {
+        let todos = vec![
+            todo("Ops", "Low", "Done", "-"),
+            todo("Ops", "High", "Blocked", "-"),
+            todo("Ops", "Medium", "Pending", "-"),
+        ];
+        let names = |group_by| {
+            rows(&todos, group_by, &HashSet::new(), &statuses())
+                .into_iter()
+                .filter_map(|row| match row {
+                    TableRow::Group { name, .. } => Some(name),
+                    TableRow::Todo(_) => None,
+                })
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(names(GroupBy::Priority), ["High", "Medium", "Low"]);
+        // Statuses missing from the board go last
+        assert_eq!(names(GroupBy::Status), ["Pending", "Done", "Blocked"]);
+    }
+
+    #[test]
+    fn due_dates_are_bucketed() {
+        let today = Local::now().date_naive();
+        let day = |days: i64| (today + chrono::Duration::days(days)).to_string();
+        let todos = [
+            todo("Ops", "Low", "Pending", &day(30)),
+            todo("Ops", "Low", "Pending", &day(-1)),
+            todo("Ops", "Low", "Done", &day(-1)),
+            todo("Ops", "Low", "Pending", "-"),
+            todo("Ops", "Low", "Pending", &day(0)),
+            todo("Ops", "Low", "Pending", &day(3)),
+        ];
+        let keys = todos
+            .iter()
+            .map(|todo| GroupBy::Due.key(todo))
+            .collect::<Vec<String>>();
+        assert_eq!(
+            keys,
+            [
+                "Later",
+                "Overdue",
+                "Earlier",
+                "No due date",
+                "Today",
+                "This week"
+            ]
+        );
+    }
+
+    #[test]
+    fn collapsed_groups_hide_their_todos() {
+        let todos = vec![
+            todo("Ops", "Low", "Pending", "-"),
+            todo("Docs", "Low", "Pending", "-"),
+        ];
+        let collapsed = HashSet::from(["Ops".to_string()]);
+        assert_eq!(
+            rows(&todos, GroupBy::Topic, &collapsed, &statuses()),
+            [
+                group("Docs", 1, 0, false),
+                TableRow::Todo(1),
+                group("Ops", 1, 0, true),
+            ]
+        );
+    }
+}
@@ -0,0 +1,678 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+/// What a key does in the TUI, named as in the `[TUI.keys]` section
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    Open,
+    Clear,
+    Filter,
+    NextMatch,
+    PreviousMatch,
+    NextView,
+    Group,
+    NextGroup,
+    PreviousGroup,
+    MoveLeft,
+    MoveRight,
+    Mark,
+    MarkRange,
+    MarkAll,
+    Add,
+    Edit,
+    Pending,
+    Ongoing,
+    Done,
+    Delete,
+    Owner,
+    Topic,
+    Due,
+    PreviousMonth,
+    NextMonth,
+    PreviousInDay,
+    NextInDay,
+    DueLater,
+    DueEarlier,
+    DueWeekLater,
+    DueWeekEarlier,
+    Ai,
+    Help,
+    Quit,
+    AiSummarise,
+    AiSuggestNext,
+    AiRewrite,
+    AiPrompt,
+    AiClose,
+    AiScrollUp,
+    AiScrollDown,
+    AiPageUp,
+    AiPageDown,
+    AiNextTodo,
+    AiPreviousTodo,
+    Yes,
+    No,
+}
+
+/// Where an action's keys work, the same key can do something else in each
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Main,
+    AiPanel,
+    Confirm,
+}
+
+/// The help overlay's sections, left column first
+pub const SECTIONS: [(&str, &[Action]); 9] = [
+    (
+        "Navigation",
+        &[
+            Action::Up,
+            Action::Down,
+            Action::Left,
+            Action::Right,
+            Action::Open,
+            Action::Clear,
+            Action::Filter,
+            Action::NextMatch,
+            Action::PreviousMatch,
+        ],
+    ),
+    (
+        "Views",
+        &[
+            Action::NextView,
+            Action::Group,
+            Action::NextGroup,
+            Action::PreviousGroup,
+        ],
+    ),
+    ("Board", &[Action::MoveLeft, Action::MoveRight]),
+    (
+        "Selection",
+        &[Action::Mark, Action::MarkRange, Action::MarkAll],
+    ),
+    (
+        "Todos",
+        &[
+            Action::Add,
+            Action::Edit,
+            Action::Pending,
+            Action::Ongoing,
+            Action::Done,
+            Action::Delete,
+            Action::Owner,
+            Action::Topic,
+            Action::Due,
+        ],
+    ),
+    (
+        "Calendar",
+        &[
+            Action::PreviousMonth,
+            Action::NextMonth,
+            Action::PreviousInDay,
+            Action::NextInDay,
+            Action::DueLater,
+            Action::DueEarlier,
+            Action::DueWeekLater,
+            Action::DueWeekEarlier,
+        ],
+    ),
+    ("General", &[Action::Ai, Action::Help, Action::Quit]),
+    (
+        "AI panel",
+        &[
+            Action::AiSummarise,
+            Action::AiSuggestNext,
+            Action::AiRewrite,
+            Action::AiPrompt,
+            Action::AiClose,
+            Action::AiScrollUp,
+            Action::AiScrollDown,
+            Action::AiPageUp,
+            Action::AiPageDown,
+            Action::AiNextTodo,
+            Action::AiPreviousTodo,
+        ],
+    ),
+    ("Confirmations", &[Action::Yes, Action::No]),
+];
+
+impl Action {
+    pub fn all() -> impl Iterator<Item = Action> {
+        SECTIONS
+            .iter()
+            .flat_map(|(_, actions)| actions.iter().copied())
+    }
+
+    pub fn scope(&self) -> Scope {
+        match self {
+            Action::AiSummarise
+            | Action::AiSuggestNext
+            | Action::AiRewrite
+            | Action::AiPrompt
+            | Action::AiClose
+            | Action::AiScrollUp
+            | Action::AiScrollDown
+            | Action::AiPageUp
+            | Action::AiPageDown
+            | Action::AiNextTodo
+            | Action::AiPreviousTodo => Scope::AiPanel,
+            Action::Yes | Action::No => Scope::Confirm,
+            _ => Scope::Main,
+        }
+    }
+
+    // The name used in config.toml
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Open => "open",
+            Action::Clear => "clear",
+            Action::Filter => "filter",
+            Action::NextMatch => "next_match",
+            Action::PreviousMatch => "previous_match",
+            Action::NextView => "next_view",
+            Action::Group => "group",
+            Action::NextGroup => "next_group",
+            Action::PreviousGroup => "previous_group",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Mark => "mark",
+            Action::MarkRange => "mark_range",
+            Action::MarkAll => "mark_all",
+            Action::Add => "add",
+            Action::Edit => "edit",
+            Action::Pending => "pending",
+            Action::Ongoing => "ongoing",
+            Action::Done => "done",
+            Action::Delete => "delete",
+            Action::Owner => "owner",
+            Action::Topic => "topic",
+            Action::Due => "due",
+            Action::PreviousMonth => "previous_month",
+            Action::NextMonth => "next_month",
+            Action::PreviousInDay => "previous_in_day",
+            Action::NextInDay => "next_in_day",
+            Action::DueLater => "due_later",
+            Action::DueEarlier => "due_earlier",
+            Action::DueWeekLater => "due_week_later",
+            Action::DueWeekEarlier => "due_week_earlier",
+            Action::Ai => "ai",
+            Action::Help => "help",
+            Action::Quit => "quit",
+            Action::AiSummarise => "ai_summarise",
+            Action::AiSuggestNext => "ai_suggest_next",
+            Action::AiRewrite => "ai_rewrite",
+            Action::AiPrompt => "ai_prompt",
+            Action::AiClose => "ai_close",
+            Action::AiScrollUp => "ai_scroll_up",
+            Action::AiScrollDown => "ai_scroll_down",
+            Action::AiPageUp => "ai_page_up",
+            Action::AiPageDown => "ai_page_down",
+            Action::AiNextTodo => "ai_next_todo",
+            Action::AiPreviousTodo => "ai_previous_todo",
+            Action::Yes => "yes",
+            Action::No => "no",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Up => "Previous todo, card or week",
+            Action::Down => "Next todo, card or week",
+            Action::Left => "Previous column or day",
+            Action::Right => "Next column or day",
+            Action::Open => "Show details, fold a group",
+            Action::Clear => "Close, clear marks or filter",
+            Action::Filter => "Filter the todos",
+            Action::NextMatch => "Next match",
+            Action::PreviousMatch => "Previous match",
+            Action::NextView => "Table, board, calendar",
+            Action::Group => "Group the table",
+            Action::NextGroup => "Next group",
+            Action::PreviousGroup => "Previous group",
+            Action::MoveLeft => "Move card to previous status",
+            Action::MoveRight => "Move card to next status",
+            Action::Mark => "Mark or unmark",
+            Action::MarkRange => "Start or end a range",
+            Action::MarkAll => "Mark all shown",
+            Action::Add => "Add a todo",
+            Action::Edit => "Edit the todo",
+            Action::Pending => "Mark as Pending",
+            Action::Ongoing => "Mark as Ongoing",
+            Action::Done => "Mark as Done",
+            Action::Delete => "Delete",
+            Action::Owner => "Reassign owner",
+            Action::Topic => "Change topic",
+            Action::Due => "Set due date",
+            Action::PreviousMonth => "Previous month",
+            Action::NextMonth => "Next month",
+            Action::PreviousInDay => "Previous todo of the day",
+            Action::NextInDay => "Next todo of the day",
+            Action::DueLater => "Due a day later",
+            Action::DueEarlier => "Due a day earlier",
+            Action::DueWeekLater => "Due a week later",
+            Action::DueWeekEarlier => "Due a week earlier",
+            Action::Ai => "AI panel",
+            Action::Help => "This help",
+            Action::Quit => "Quit",
+            Action::AiSummarise => "Summarise the selected todo",
+            Action::AiSuggestNext => "Suggest the next task",
+            Action::AiRewrite => "Rewrite the description",
+            Action::AiPrompt => "Write your own prompt",
+            Action::AiClose => "Stop the answer or close",
+            Action::AiScrollUp => "Scroll up",
+            Action::AiScrollDown => "Scroll down",
+            Action::AiPageUp => "Scroll a page up",
+            Action::AiPageDown => "Scroll a page down",
+            Action::AiNextTodo => "Select the next todo",
+            Action::AiPreviousTodo => "Select the previous todo",
+            Action::Yes => "Confirm",
+            Action::No => "Cancel",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Up => &["up", "k"],
+            Action::Down => &["down", "j"],
+            Action::Left => &["left", "h"],
+            Action::Right => &["right", "l"],
+            Action::Open => &["enter"],
+            Action::Clear => &["esc"],
+            Action::Filter => &["/"],
+            Action::NextMatch => &["n"],
+            Action::PreviousMatch => &["N"],
+            Action::NextView => &["tab"],
+            Action::Group => &["g"],
+            Action::NextGroup => &["}"],
+            Action::PreviousGroup => &["{"],
+            Action::MoveLeft => &["H"],
+            Action::MoveRight => &["L"],
+            Action::Mark => &["space"],
+            Action::MarkRange => &["V"],
+            Action::MarkAll => &["*"],
+            Action::Add => &["a"],
+            Action::Edit => &["e"],
+            Action::Pending => &["p"],
+            Action::Ongoing => &["o"],
+            Action::Done => &["d"],
+            Action::Delete => &["delete"],
+            Action::Owner => &["r"],
+            Action::Topic => &["t"],
+            Action::Due => &["D"],
+            Action::PreviousMonth => &["pageup"],
+            Action::NextMonth => &["pagedown"],
+            Action::PreviousInDay => &["K"],
+            Action::NextInDay => &["J"],
+            Action::DueLater => &["+"],
+            Action::DueEarlier => &["-"],
+            Action::DueWeekLater => &[">"],
+            Action::DueWeekEarlier => &["<"],
+            Action::Ai => &["A"],
+            Action::Help => &["?"],
+            Action::Quit => &["q"],
+            Action::AiSummarise => &["s"],
+            Action::AiSuggestNext => &["n"],
+            Action::AiRewrite => &["r"],
+            Action::AiPrompt => &["/", "i"],
+            Action::AiClose => &["esc", "q", "A"],
+            Action::AiScrollUp => &["up", "k"],
+            Action::AiScrollDown => &["down", "j"],
+            Action::AiPageUp => &["pageup"],
+            Action::AiPageDown => &["pagedown"],
+            Action::AiNextTodo => &["tab"],
+            Action::AiPreviousTodo => &["backtab"],
+            Action::Yes => &["y", "enter"],
+            Action::No => &["n", "esc"],
+        }
+    }
+}
+
+/// A key with its modifiers, e.g. `ctrl-d`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl Key {
+    // "d", "D", "ctrl-d", "alt+enter", "shift-tab", "pageup", "f5"
+    pub fn parse(text: &str) -> Option<Key> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text.trim();
+        while rest.chars().count() > 1 {
+            let lower = rest.to_lowercase();
+            let modifier = [
+                ("ctrl", KeyModifiers::CONTROL),
+                ("alt", KeyModifiers::ALT),
+                ("shift", KeyModifiers::SHIFT),
+            ]
+            .into_iter()
+            .find(|(name, _)| {
+                lower.starts_with(name)
+                    && matches!(lower[name.len()..].chars().next(), Some('-' | '+'))
+            });
+            let Some((name, modifier)) = modifier else {
+                break;
+            };
+            modifiers |= modifier;
+            rest = &rest[name.len() + 1..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "space" => KeyCode::Char(' '),
+                "delete" | "del" => KeyCode::Delete,
+                "backspace" => KeyCode::Backspace,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => KeyCode::F(name.strip_prefix('f')?.parse().ok()?),
+            },
+        };
+        Some(Self::new(code, modifiers))
+    }
+
+    // Shift is part of the character (`D`) or of the key (BackTab), not a modifier of its own
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let shift = modifiers.contains(KeyModifiers::SHIFT);
+        let modifiers = modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+        let code = match code {
+            KeyCode::Char(c) if shift => KeyCode::Char(c.to_ascii_uppercase()),
+            KeyCode::Tab if shift => KeyCode::BackTab,
+            code => code,
+        };
+        Self { code, modifiers }
+    }
+
+    pub fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::Enter => "Enter".to_string(),
+            KeyCode::Esc => "Esc".to_string(),
+            KeyCode::Tab => "Tab".to_string(),
+            KeyCode::BackTab => "Shift-Tab".to_string(),
+            KeyCode::Delete => "Del".to_string(),
+            KeyCode::Backspace => "Backspace".to_string(),
+            KeyCode::Insert => "Ins".to_string(),
+            KeyCode::Up => "↑".to_string(),
+            KeyCode::Down => "↓".to_string(),
+            KeyCode::Left => "←".to_string(),
+            KeyCode::Right => "→".to_string(),
+            KeyCode::Home => "Home".to_string(),
+            KeyCode::End => "End".to_string(),
+            KeyCode::PageUp => "PgUp".to_string(),
+            KeyCode::PageDown => "PgDn".to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            code => format!("{:?}", code),
+        };
+        let mut label = String::new();
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            label.push_str("Ctrl-");
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            label.push_str("Alt-");
+        }
+        label + &key
+    }
+}
+
+impl From<KeyEvent> for Key {
+    fn from(event: KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+/// Which keys run which action, the defaults with the `[TUI.keys]` overrides on top
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<Key>)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self {
+            bindings: Action::all()
+                .map(|action| {
+                    let keys = action
+                        .default_keys()
+                        .iter()
+                        .filter_map(|key| Key::parse(key))
+                        .collect();
+                    (action, keys)
+                })
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    pub fn action(&self, scope: Scope, event: KeyEvent) -> Option<Action> {
+        let key = Key::from(event);
+        self.bindings
+            .iter()
+            .find(|(action, keys)| action.scope() == scope && keys.contains(&key))
+            .map(|(action, _)| *action)
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map(|(_, keys)| keys.as_slice())
+            .unwrap_or_default()
+    }
+
+    // "k/↑", empty when the action has no key. "/, i" when one of the keys is `/`
+    pub fn label(&self, action: Action) -> String {
+        let labels = self
+            .keys(action)
+            .iter()
+            .map(Key::label)
+            .collect::<Vec<String>>();
+        let separator = if labels.iter().any(|label| label == "/") {
+            ", "
+        } else {
+            "/"
+        };
+        labels.join(separator)
+    }
+
+    /// Replace the keys of the action named `name`, the keys are taken from any other action
+    /// in the same scope
+    pub fn bind(&mut self, name: &str, keys: &[&str]) -> Result<(), String> {
+        let action = Action::all()
+            .find(|action| action.name() == name)
+            .ok_or_else(|| format!("Unknown action '{}' in [TUI.keys]", name))?;
+        let keys = keys
+            .iter()
+            .map(|key| Key::parse(key).ok_or_else(|| format!("Unknown key '{}' for {}", key, name)))
+            .collect::<Result<Vec<Key>, String>>()?;
+
+        for (bound, bound_keys) in self.bindings.iter_mut() {
+            if *bound == action {
+                *bound_keys = keys.clone();
+            } else if bound.scope() == action.scope() {
+                bound_keys.retain(|key| !keys.contains(key));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Option<Key> {
+        Some(Key { code, modifiers })
+    }
+
+    fn pressed(
+        keymap: &Keymap,
+        scope: Scope,
+        code: KeyCode,
+        modifiers: KeyModifiers,
+    ) -> Option<Action> {
+        keymap.action(scope, KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn characters_and_named_keys_are_parsed() {
+        let none = KeyModifiers::NONE;
+        assert_eq!(Key::parse("d"), key(KeyCode::Char('d'), none));
+        assert_eq!(Key::parse("D"), key(KeyCode::Char('D'), none));
+        assert_eq!(Key::parse("-"), key(KeyCode::Char('-'), none));
+        assert_eq!(Key::parse(" Enter "), key(KeyCode::Enter, none));
+        assert_eq!(Key::parse("escape"), key(KeyCode::Esc, none));
+        assert_eq!(Key::parse("space"), key(KeyCode::Char(' '), none));
+        assert_eq!(Key::parse("PageDown"), key(KeyCode::PageDown, none));
+        assert_eq!(Key::parse("f5"), key(KeyCode::F(5), none));
+    }
+
+    #[test]
+    fn modifiers_are_parsed() {
+        assert_eq!(
+            Key::parse("ctrl-d"),
+            key(KeyCode::Char('d'), KeyModifiers::CONTROL)
+        );
+        assert_eq!(
+            Key::parse("Alt+Enter"),
+            key(KeyCode::Enter, KeyModifiers::ALT)
+        );
+        assert_eq!(
+            Key::parse("ctrl-alt-x"),
+            key(
+                KeyCode::Char('x'),
+                KeyModifiers::CONTROL | KeyModifiers::ALT
+            )
+        );
+        // Shift goes into the key itself
+        assert_eq!(Key::parse("shift-d"), Key::parse("D"));
+        assert_eq!(Key::parse("shift-tab"), Key::parse("backtab"));
+        // A dash on its own is a key, not a modifier
+        assert_eq!(
+            Key::parse("ctrl--"),
+            key(KeyCode::Char('-'), KeyModifiers::CONTROL)
+        );
+    }
+
+    #[test]
+    fn invalid_keys_are_rejected() {
+        assert_eq!(Key::parse(""), None);
+        assert_eq!(Key::parse("ctrl-"), None);
+        assert_eq!(Key::parse("hyper-d"), None);
+        assert_eq!(Key::parse("enterr"), None);
+        assert_eq!(Key::parse("fx"), None);
+    }
+
+    #[test]
+    fn key_events_match_their_parsed_key() {
+        let keymap = Keymap::default();
+        let main = |code, modifiers| pressed(&keymap, Scope::Main, code, modifiers);
+        assert_eq!(
+            main(KeyCode::Char('d'), KeyModifiers::NONE),
+            Some(Action::Done)
+        );
+        // Terminals report shift with upper case letters
+        assert_eq!(
+            main(KeyCode::Char('D'), KeyModifiers::SHIFT),
+            Some(Action::Due)
+        );
+        assert_eq!(main(KeyCode::Char('d'), KeyModifiers::CONTROL), None);
+        // The key that opens the AI panel closes it again
+        assert_eq!(
+            pressed(
+                &keymap,
+                Scope::AiPanel,
+                KeyCode::Char('A'),
+                KeyModifiers::SHIFT
+            ),
+            Some(Action::AiClose)
+        );
+    }
+
+    #[test]
+    fn binding_a_key_takes_it_from_other_actions_in_the_same_scope() {
+        let mut keymap = Keymap::default();
+        keymap.bind("done", &["ctrl-d", "x"]).unwrap();
+        keymap.bind("quit", &["d"]).unwrap();
+        let main =
+            |keymap: &Keymap, c| pressed(keymap, Scope::Main, KeyCode::Char(c), KeyModifiers::NONE);
+        assert_eq!(
+            pressed(
+                &keymap,
+                Scope::Main,
+                KeyCode::Char('d'),
+                KeyModifiers::CONTROL
+            ),
+            Some(Action::Done)
+        );
+        assert_eq!(main(&keymap, 'd'), Some(Action::Quit));
+        assert_eq!(main(&keymap, 'q'), None);
+
+        // `n` in the table is still next match, in a confirmation it stops being no
+        keymap.bind("yes", &["n"]).unwrap();
+        assert_eq!(main(&keymap, 'n'), Some(Action::NextMatch));
+        assert_eq!(
+            pressed(
+                &keymap,
+                Scope::Confirm,
+                KeyCode::Char('n'),
+                KeyModifiers::NONE
+            ),
+            Some(Action::Yes)
+        );
+        assert_eq!(keymap.label(Action::No), "Esc");
+    }
+
+    #[test]
+    fn unknown_actions_and_keys_are_errors() {
+        let mut keymap = Keymap::default();
+        assert_eq!(
+            keymap.bind("fly", &["f"]),
+            Err("Unknown action 'fly' in [TUI.keys]".to_string())
+        );
+        assert_eq!(
+            keymap.bind("done", &["x", "ctrl-"]),
+            Err("Unknown key 'ctrl-' for done".to_string())
+        );
+        // A failed binding leaves the keys as they were
+        assert_eq!(keymap.label(Action::Done), "d");
+    }
+
+    #[test]
+    fn labels_join_the_keys() {
+        let mut keymap = Keymap::default();
+        assert_eq!(keymap.label(Action::Up), "↑/k");
+        assert_eq!(keymap.label(Action::AiPrompt), "/, i");
+        assert_eq!(keymap.label(Action::AiPreviousTodo), "Shift-Tab");
+        keymap
+            .bind("done", &["ctrl-d", "alt-pageup", "space"])
+            .unwrap();
+        assert_eq!(keymap.label(Action::Done), "Ctrl-d/Alt-PgUp/Space");
+        keymap.bind("done", &[]).unwrap();
+        assert_eq!(keymap.label(Action::Done), "");
+    }
+}
@@ -1,14 +1,23 @@
+use ai::panel::{AiPanel, Focus};
 use arguments::{
+    add_todo::Added,
     delete_todo,
-    models::{self, Cli, Todo},
+    models::{self, AiCommand, Cli, Commands, Todo},
 };
+use bulk::{BulkAction, BulkPrompt, Confirm, PromptAction, PromptField};
 use clap::Parser;
+use colors::Theme;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{DisableMouseCapture, EnableMouseCapture, KeyCode, KeyEvent},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
 use data::sample_todos;
+use events::{AppEvent, Events};
+use filter::Filter;
+use form::{FormAction, TodoForm};
+use groups::{GroupBy, TableRow};
+use keymap::{Action, Keymap, Scope};
 use ratatui::prelude::Stylize;
 use ratatui::widgets::TableState;
 use ratatui::{
@@ -19,42 +28,123 @@ use ratatui::{
     text::Line,
     widgets::{Block, Borders, Paragraph, Row, Table, Wrap},
 };
-use std::io;
+use status::StatusMessage;
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::sync::Arc;
 use ui::{calculate_stats, draw_ui};
 
 mod ai; // LLMS stuff
 mod args; // Print all the args available in the App so it does not clutter the main.rs
 mod arguments;
+mod board; // Kanban board view
+mod bulk; // Bulk actions on the marked todos
+mod calendar; // Calendar of due dates
 mod colors;
 mod configs;
+mod credentials; // API key lookup and storage
 mod data; // DATABASE STUFF;
 mod database;
+mod events; // Keys, timers and background results for the TUI loop
+mod filter; // Filter bar of the TUI
+mod form; // Add and edit form state
+mod groups; // Grouped sections of the TUI table
+mod keymap; // Configurable keys of the TUI
 mod modals; // All the modals logic
+mod status; // Status bar messages
 mod ui; // ALL THE UI STUFF
 mod xls;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum View {
+    Table,
+    Board,
+    Calendar,
+}
+
 #[derive(Debug)]
 pub struct App {
+    /// The todos shown, narrowed by the filter
     pub todos: Vec<Todo>,
+    pub all_todos: Vec<Todo>,
     pub state: TableState,
     pub show_modal: bool,
     pub selected_todo: Option<Todo>,
     pub show_delete_confirmation: bool,
+    pub ai_panel: Option<AiPanel>,
+    pub form: Option<TodoForm>,
+    pub filter: Filter,
+    pub view: View,
+    /// Focused column of the board, may be empty
+    pub board_column: usize,
+    /// Board columns from the `[TUI]` settings
+    pub statuses: Vec<String>,
+    /// Selected day of the calendar
+    pub calendar_day: chrono::NaiveDate,
+    pub group_by: GroupBy,
+    /// Names of the folded groups
+    pub collapsed: HashSet<String>,
+    // The group header under the cursor, only while no todo is selected
+    group_header: Option<String>,
+    /// Ids of the todos marked for a bulk action
+    pub marked: HashSet<usize>,
+    /// Id where a `V` range starts
+    pub range_anchor: Option<usize>,
+    pub confirm: Option<Confirm>,
+    pub prompt: Option<BulkPrompt>,
+    /// Last success or error, shown in the status bar until it expires
+    pub status: Option<StatusMessage>,
+    pub theme: Theme,
+    pub keymap: Keymap,
+    pub show_help: bool,
 }
 
 impl App {
     fn new(todos: Vec<Todo>) -> Self {
         let mut state = TableState::default();
         state.select(Some(0)); // Select first item by default
+        let tui = configs::AppConfigs::new()
+            .map(|configs| configs.tui)
+            .unwrap_or_default();
         Self {
+            all_todos: todos.clone(),
             todos,
             state,
             show_modal: false,
             selected_todo: None,
             show_delete_confirmation: false,
+            ai_panel: None,
+            form: None,
+            filter: Filter::default(),
+            view: View::Table,
+            board_column: 0,
+            statuses: tui.statuses,
+            calendar_day: chrono::Local::now().date_naive(),
+            group_by: GroupBy::None,
+            collapsed: HashSet::new(),
+            group_header: None,
+            marked: HashSet::new(),
+            range_anchor: None,
+            confirm: None,
+            prompt: None,
+            // Bad [TUI.keys] entries, the defaults are used instead
+            status: (!tui.warnings.is_empty())
+                .then(|| StatusMessage::error(tui.warnings.join("; "))),
+            theme: tui.theme,
+            keymap: tui.keymap,
+            show_help: false,
         }
     }
 
+    // Show a message in the status bar
+    fn notify(&mut self, text: String) {
+        self.status = Some(StatusMessage::success(text));
+    }
+
+    fn notify_error(&mut self, text: String) {
+        self.status = Some(StatusMessage::error(text));
+    }
+
     // CHANGE TODO STATUS
     fn change_todo_status(
         &mut self,
@@ -74,13 +164,16 @@ impl App {
         db.update_todo(id, Some(status.clone()))?;
 
         // Update local state
-        self.todos[selected].status = status;
-
-        // Maintain selection position
-        if !self.todos.is_empty() {
-            let new_selection = selected.min(self.todos.len().saturating_sub(1));
-            self.state.select(Some(new_selection));
+        if let Some(todo) = self
+            .all_todos
+            .iter_mut()
+            .find(|todo| todo.id == id as usize)
+        {
+            todo.status = status.clone();
         }
+        self.notify(format!("#{} marked as {}", id, status));
+        // A status filter may hide it now
+        self.apply_filter();
 
         Ok(())
     }
@@ -93,20 +186,21 @@ impl App {
                 db.delete_todo(id as i32)?;
 
                 // Update local state
-                self.todos.remove(selected);
-
-                // Adjust selection
-                if !self.todos.is_empty() {
-                    self.state.select(Some(selected.min(self.todos.len() - 1)));
-                } else {
-                    self.state.select(None);
-                }
+                self.all_todos.retain(|todo| todo.id != id);
+                self.apply_filter();
+                self.notify(format!("Deleted #{}", id));
             }
         }
         Ok(())
     }
 
     fn next(&mut self) {
+        if self.group_by != GroupBy::None {
+            return self.move_row(1);
+        }
+        if self.todos.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i >= self.todos.len() - 1 {
@@ -121,6 +215,12 @@ impl App {
     }
 
     fn previous(&mut self) {
+        if self.group_by != GroupBy::None {
+            return self.move_row(-1);
+        }
+        if self.todos.is_empty() {
+            return;
+        }
         let i = match self.state.selected() {
             Some(i) => {
                 if i == 0 {
@@ -147,6 +247,493 @@ impl App {
         self.show_modal = false;
         self.selected_todo = None;
     }
+
+    fn selected(&self) -> Option<&Todo> {
+        self.todos.get(self.state.selected()?)
+    }
+
+    // Reload after the AI panel or another process changed the database
+    fn reload_todos(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let db = database::DBtodo::new()?;
+        self.all_todos = db.get_todos()?;
+        self.marked
+            .retain(|id| self.all_todos.iter().any(|todo| todo.id == *id));
+        self.apply_filter();
+        Ok(())
+    }
+
+    // Narrow the table to the filter, the selection stays on the same todo while it matches
+    fn apply_filter(&mut self) {
+        let on_header = self.selected_group().is_some();
+        let selected = self.state.selected();
+        let selected_id = self.selected().map(|todo| todo.id);
+
+        self.todos = self
+            .all_todos
+            .iter()
+            .filter(|todo| self.filter.matches(todo))
+            .cloned()
+            .collect();
+
+        let position = selected_id
+            .and_then(|id| self.todos.iter().position(|todo| todo.id == id))
+            .or_else(|| {
+                (!self.todos.is_empty()).then(|| selected.unwrap_or(0).min(self.todos.len() - 1))
+            });
+        self.state.select(if on_header { None } else { position });
+    }
+
+    // THE GROUPED TABLE
+    fn table_rows(&self) -> Vec<TableRow> {
+        groups::rows(&self.todos, self.group_by, &self.collapsed, &self.statuses)
+    }
+
+    // The header under the cursor, selecting a todo moves the cursor off it
+    fn selected_group(&self) -> Option<&String> {
+        match self.state.selected() {
+            Some(_) => None,
+            None => self.group_header.as_ref(),
+        }
+    }
+
+    // Row of the cursor, a todo hidden in a folded group puts it on the group's header
+    fn current_row(&self, rows: &[TableRow]) -> Option<usize> {
+        let header = |name: &str| {
+            rows.iter()
+                .position(|row| matches!(row, TableRow::Group { name: n, .. } if n == name))
+        };
+        if let Some(name) = self.selected_group() {
+            return header(name);
+        }
+        let selected = self.state.selected()?;
+        rows.iter()
+            .position(|row| *row == TableRow::Todo(selected))
+            .or_else(|| header(&self.group_by.key(self.todos.get(selected)?)))
+    }
+
+    fn select_row(&mut self, row: &TableRow) {
+        match row {
+            TableRow::Todo(index) => self.state.select(Some(*index)),
+            TableRow::Group { name, .. } => {
+                self.state.select(None);
+                self.group_header = Some(name.clone());
+            }
+        }
+    }
+
+    // j/k over headers and todos alike
+    fn move_row(&mut self, step: isize) {
+        let rows = self.table_rows();
+        if rows.is_empty() {
+            return;
+        }
+        let target = match self.current_row(&rows) {
+            Some(row) => (row as isize + step).rem_euclid(rows.len() as isize) as usize,
+            None => 0,
+        };
+        self.select_row(&rows[target]);
+    }
+
+    // {/}: jump to the previous or next group header, skipping its todos
+    fn move_group(&mut self, step: isize) {
+        let rows = self.table_rows();
+        let current = self.current_row(&rows).unwrap_or(0) as isize;
+        let is_header = |row: &TableRow| matches!(row, TableRow::Group { .. });
+        let target = if step > 0 {
+            (current as usize + 1..rows.len()).find(|i| is_header(&rows[*i]))
+        } else {
+            (0..current.max(0) as usize)
+                .rev()
+                .find(|i| is_header(&rows[*i]))
+        };
+        if let Some(target) = target {
+            self.select_row(&rows[target]);
+        }
+    }
+
+    // Enter on a header folds or unfolds its group
+    fn toggle_group(&mut self) {
+        if let Some(name) = self.selected_group().cloned()
+            && !self.collapsed.remove(&name)
+        {
+            self.collapsed.insert(name);
+        }
+    }
+
+    fn cycle_group_by(&mut self) {
+        self.group_by = self.group_by.next();
+        self.collapsed.clear();
+        if self.state.selected().is_none() && !self.todos.is_empty() {
+            self.state.select(Some(0));
+        }
+    }
+
+    fn toggle_view(&mut self) {
+        self.view = match self.view {
+            View::Table => View::Board,
+            View::Board => View::Calendar,
+            View::Calendar => View::Table,
+        };
+        // A board column or a day can be empty, the table always has a selection
+        if self.state.selected().is_none() && !self.todos.is_empty() {
+            self.state.select(Some(0));
+        }
+        match self.view {
+            View::Board => self.sync_board_column(),
+            View::Calendar => {
+                // Open on the selected todo's due date
+                if let Some(due) = self.selected().and_then(calendar::due_date) {
+                    self.calendar_day = due;
+                }
+                self.calendar_move_day(0);
+            }
+            View::Table => {}
+        }
+    }
+
+    // The board's focus follows the selected card when its status changes
+    fn sync_board_column(&mut self) {
+        let Some(todo) = self.selected() else {
+            return;
+        };
+        let status = todo.status.clone();
+        if let Some(column) = board::columns(&self.statuses, &self.todos)
+            .iter()
+            .position(|(column, _)| column.eq_ignore_ascii_case(&status))
+        {
+            self.board_column = column;
+        }
+    }
+
+    // h/l: focus the adjacent column, on the card at the same height
+    fn board_move_column(&mut self, step: isize) {
+        let columns = board::columns(&self.statuses, &self.todos);
+        let current = columns.get(self.board_column).map(|(_, cards)| cards);
+        let row = match (current, self.state.selected()) {
+            (Some(cards), Some(selected)) => cards.iter().position(|i| *i == selected).unwrap_or(0),
+            _ => 0,
+        };
+
+        let target = (self.board_column as isize + step).clamp(0, columns.len() as isize - 1);
+        self.board_column = target as usize;
+        let cards = &columns[self.board_column].1;
+        self.state
+            .select(cards.get(row.min(cards.len().saturating_sub(1))).copied());
+    }
+
+    // j/k: the next or previous card in the focused column
+    fn board_move_card(&mut self, step: isize) {
+        let columns = board::columns(&self.statuses, &self.todos);
+        let Some((_, cards)) = columns.get(self.board_column) else {
+            return;
+        };
+        if cards.is_empty() {
+            return;
+        }
+        let row = self
+            .state
+            .selected()
+            .and_then(|selected| cards.iter().position(|i| *i == selected))
+            .map(|row| (row as isize + step).clamp(0, cards.len() as isize - 1) as usize)
+            .unwrap_or(0);
+        self.state.select(Some(cards[row]));
+    }
+
+    // H/L: move the selected card to the adjacent status
+    fn board_move_todo(&mut self, step: isize) -> Result<(), Box<dyn std::error::Error>> {
+        let todo = self.selected().ok_or("No todo selected")?.clone();
+        let columns = board::columns(&self.statuses, &self.todos);
+        let target = self.board_column as isize + step;
+        if target < 0 || target >= columns.len() as isize {
+            return Ok(());
+        }
+        let status = columns[target as usize].0.clone();
+        self.change_todo_status(todo.id as i32, status)
+    }
+
+    // Move the calendar by `days`, selecting the first todo due that day
+    fn calendar_move_day(&mut self, days: i64) {
+        self.calendar_day += chrono::Duration::days(days);
+        let due = calendar::due_on(&self.todos, self.calendar_day);
+        if !self
+            .state
+            .selected()
+            .is_some_and(|selected| due.contains(&selected))
+        {
+            self.state.select(due.first().copied());
+        }
+    }
+
+    fn calendar_move_month(&mut self, months: i32) {
+        let day = self.calendar_day;
+        let moved = if months < 0 {
+            day.checked_sub_months(chrono::Months::new(months.unsigned_abs()))
+        } else {
+            day.checked_add_months(chrono::Months::new(months as u32))
+        };
+        self.calendar_day = moved.unwrap_or(day);
+        self.calendar_move_day(0);
+    }
+
+    // J/K: the next or previous todo due on the selected day
+    fn calendar_move_todo(&mut self, step: isize) {
+        let due = calendar::due_on(&self.todos, self.calendar_day);
+        if due.is_empty() {
+            return;
+        }
+        let row = self
+            .state
+            .selected()
+            .and_then(|selected| due.iter().position(|i| *i == selected))
+            .map(|row| (row as isize + step).clamp(0, due.len() as isize - 1) as usize)
+            .unwrap_or(0);
+        self.state.select(Some(due[row]));
+    }
+
+    // Push the selected todo's due date by `days`, the calendar follows it
+    fn move_due(&mut self, days: i64) -> Result<(), Box<dyn std::error::Error>> {
+        let mut todo = self.selected().ok_or("No todo selected")?.clone();
+        let due = calendar::due_date(&todo).ok_or("The todo has no due date")?;
+        let moved = due + chrono::Duration::days(days);
+        todo.due = moved.format("%Y-%m-%d").to_string();
+
+        let db = database::DBtodo::new()?;
+        db.edit_todo(&todo)?;
+
+        self.notify(format!("#{} is now due {}", todo.id, todo.due));
+        if let Some(existing) = self.all_todos.iter_mut().find(|t| t.id == todo.id) {
+            existing.due = todo.due;
+        }
+        self.apply_filter();
+        self.calendar_day = moved;
+        Ok(())
+    }
+
+    // MARKING TODOS FOR BULK ACTIONS
+    fn toggle_mark(&mut self) {
+        if let Some(id) = self.selected().map(|todo| todo.id)
+            && !self.marked.remove(&id)
+        {
+            self.marked.insert(id);
+        }
+    }
+
+    // The first V marks where the range starts, the second marks every row up to the cursor
+    fn mark_range(&mut self) {
+        let Some(current) = self.selected().map(|todo| todo.id) else {
+            return;
+        };
+        let Some(anchor) = self.range_anchor.take() else {
+            self.range_anchor = Some(current);
+            self.marked.insert(current);
+            return;
+        };
+
+        // In the order the table shows them
+        let order = self
+            .table_rows()
+            .iter()
+            .filter_map(|row| match row {
+                TableRow::Todo(index) => Some(self.todos[*index].id),
+                TableRow::Group { .. } => None,
+            })
+            .collect::<Vec<usize>>();
+        if let (Some(start), Some(end)) = (
+            order.iter().position(|id| *id == anchor),
+            order.iter().position(|id| *id == current),
+        ) {
+            self.marked.extend(&order[start.min(end)..=start.max(end)]);
+        }
+    }
+
+    // *: everything the filter shows
+    fn mark_all(&mut self) {
+        self.marked.extend(self.todos.iter().map(|todo| todo.id));
+    }
+
+    fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.range_anchor = None;
+    }
+
+    // The marked todos in list order, or the selected one when none are marked
+    fn bulk_targets(&self) -> Vec<usize> {
+        if self.marked.is_empty() {
+            return self.selected().map(|todo| todo.id).into_iter().collect();
+        }
+        self.all_todos
+            .iter()
+            .filter(|todo| self.marked.contains(&todo.id))
+            .map(|todo| todo.id)
+            .collect()
+    }
+
+    // Marked todos need a confirmation first, the selected one alone is changed right away
+    fn request_bulk(&mut self, action: BulkAction) {
+        let ids = self.bulk_targets();
+        if ids.is_empty() {
+            return;
+        }
+        if self.marked.is_empty() {
+            self.apply_bulk(action, ids);
+        } else {
+            self.confirm = Some(Confirm { action, ids });
+        }
+    }
+
+    fn apply_bulk(&mut self, action: BulkAction, ids: Vec<usize>) {
+        let todos = self
+            .all_todos
+            .iter()
+            .filter(|todo| ids.contains(&todo.id))
+            .cloned()
+            .collect::<Vec<Todo>>();
+        match action.apply(&todos) {
+            Ok(_) => {
+                self.clear_marks();
+                let verb = match action {
+                    BulkAction::Delete => "Deleted",
+                    _ => "Updated",
+                };
+                let count = todos.len();
+                self.notify(format!(
+                    "{} {} todo{}",
+                    verb,
+                    count,
+                    if count == 1 { "" } else { "s" }
+                ));
+            }
+            Err(e) => self.notify_error(format!("Error updating todos: {}", e)),
+        }
+        if let Err(e) = self.reload_todos() {
+            self.notify_error(format!("Error reloading todos: {}", e));
+        }
+    }
+
+    // KEYS WHILE A BULK ACTION WAITS FOR CONFIRMATION
+    fn handle_confirm_key(&mut self, key: KeyEvent) {
+        match self.keymap.action(Scope::Confirm, key) {
+            Some(Action::Yes) => {
+                if let Some(confirm) = self.confirm.take() {
+                    self.apply_bulk(confirm.action, confirm.ids);
+                }
+            }
+            Some(Action::No) => self.confirm = None,
+            _ => {}
+        }
+    }
+
+    // KEYS WHILE TYPING A NEW OWNER, TOPIC OR DUE DATE
+    fn handle_prompt_key(&mut self, key: KeyEvent) {
+        let Some(mut prompt) = self.prompt.take() else {
+            return;
+        };
+        match prompt.handle_key(key) {
+            PromptAction::Continue => self.prompt = Some(prompt),
+            PromptAction::Cancel => {}
+            PromptAction::Submit(action) => self.request_bulk(action),
+        }
+    }
+
+    // KEYS WHILE TYPING IN THE FILTER BAR
+    fn handle_filter_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc => self.filter.clear(),
+            KeyCode::Enter => self.filter.open = false,
+            KeyCode::Backspace => {
+                self.filter.query.pop();
+            }
+            KeyCode::Char(c) => self.filter.query.push(c),
+            KeyCode::Down => self.next(),
+            KeyCode::Up => self.previous(),
+            _ => return,
+        }
+        self.apply_filter();
+    }
+
+    // KEYS WHILE THE ADD/EDIT FORM IS OPEN
+    fn handle_form_key(&mut self, key: KeyEvent) {
+        let Some(mut form) = self.form.take() else {
+            return;
+        };
+
+        match form.handle_key(key) {
+            FormAction::Continue => self.form = Some(form),
+            FormAction::Cancel => {}
+            FormAction::Saved(id) => {
+                self.notify(format!("Saved #{}", id));
+                if let Err(e) = self.reload_todos() {
+                    self.notify_error(format!("Error reloading todos: {}", e));
+                }
+                // Follow the saved todo in the table
+                if let Some(index) = self.todos.iter().position(|todo| todo.id == id) {
+                    self.state.select(Some(index));
+                }
+            }
+        }
+    }
+
+    // KEYS WHILE THE AI PANEL IS OPEN
+    fn handle_ai_key(&mut self, key: KeyEvent) {
+        let Some(mut panel) = self.ai_panel.take() else {
+            return;
+        };
+
+        match panel.focus {
+            Focus::Input => match key.code {
+                KeyCode::Enter => panel.submit(&self.all_todos),
+                KeyCode::Esc => panel.focus = Focus::Actions,
+                KeyCode::Backspace => {
+                    panel.input.pop();
+                }
+                KeyCode::Char(c) => panel.input.push(c),
+                _ => {}
+            },
+            Focus::Actions => match self.keymap.action(Scope::AiPanel, key) {
+                Some(Action::AiClose) if panel.is_busy() => panel.cancel(),
+                Some(Action::AiClose) => return,
+                Some(Action::AiPrompt) => panel.focus = Focus::Input,
+                Some(Action::AiSuggestNext) => panel.suggest_next(&self.all_todos),
+                Some(action @ (Action::AiSummarise | Action::AiRewrite)) => match self.selected() {
+                    Some(todo) if action == Action::AiSummarise => {
+                        panel.summarise(&self.all_todos, todo)
+                    }
+                    Some(todo) => panel.rewrite_description(&self.all_todos, todo),
+                    None => panel.error = Some("No todo selected".to_string()),
+                },
+                Some(Action::AiScrollDown) => {
+                    let lines = panel.response.lines().count() as u16;
+                    panel.scroll = (panel.scroll + 1).min(lines.saturating_sub(1));
+                }
+                Some(Action::AiScrollUp) => panel.scroll = panel.scroll.saturating_sub(1),
+                Some(Action::AiPageDown) => {
+                    let lines = panel.response.lines().count() as u16;
+                    panel.scroll = (panel.scroll + 10).min(lines.saturating_sub(1));
+                }
+                Some(Action::AiPageUp) => panel.scroll = panel.scroll.saturating_sub(10),
+                // The table keeps working underneath
+                Some(Action::AiNextTodo) => self.next(),
+                Some(Action::AiPreviousTodo) => self.previous(),
+                // Suggestions are applied by their number
+                _ => match key.code {
+                    KeyCode::Char(c @ '1'..='9') if !panel.is_busy() => {
+                        let number = c.to_digit(10).unwrap_or(0) as usize;
+                        match panel.apply(number) {
+                            Ok(_) => {
+                                if let Err(e) = self.reload_todos() {
+                                    panel.error = Some(e.to_string());
+                                }
+                            }
+                            Err(e) => panel.error = Some(e.to_string()),
+                        }
+                    }
+                    _ => {}
+                },
+            },
+        }
+
+        self.ai_panel = Some(panel);
+    }
 }
 
 #[tokio::main]
@@ -154,6 +741,11 @@ async fn main() -> Result<(), io::Error> {
     // Create the configs
     let _ = configs::AppConfigs::create_default_config();
 
+    // Older versions kept the API key in plaintext inside todos.db
+    if let Err(e) = credentials::migrate_plaintext_key() {
+        eprintln!("Error migrating API key: {}", e);
+    }
+
     let cli = Cli::parse();
 
     // Check if no arguments were provided
@@ -161,6 +753,9 @@ async fn main() -> Result<(), io::Error> {
 
     // Terminal UI mode (default when no args provided or when --list is explicitly set)
     if cli.list || no_args_provided {
+        // Before raw mode, api_key_cmd may prompt for a passphrase
+        let ai_client = ai::client(false).map(Arc::from).map_err(|e| e.to_string());
+
         enable_raw_mode()?;
         let mut stdout = io::stdout();
         execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -168,83 +763,224 @@ async fn main() -> Result<(), io::Error> {
         let mut terminal = Terminal::new(backend)?;
         let todos = sample_todos();
         let mut app = App::new(todos);
+        let mut events = Events::new();
 
         loop {
+            if app.view == View::Board {
+                app.sync_board_column();
+            }
             terminal.draw(|f| draw_ui(f, &mut app))?;
-            if let Event::Key(key) = event::read()? {
-                match key.code {
-                    // CHANGE TODO STATUS
-                    KeyCode::Char('p') => {
-                        if let Some(selected) = app.state.selected() {
-                            if selected < app.todos.len() {
-                                let id = app.todos[selected].id;
-                                let status = "Pending".to_string();
-                                if let Err(e) = app.change_todo_status(id as i32, status) {
-                                    eprintln!("Error updating todo status: {}", e);
-                                }
-                            }
+
+            // Waits without blocking the runtime, so AI answers keep streaming in
+            let Some(event) = events.next().await else {
+                break;
+            };
+            let key = match event {
+                AppEvent::Key(key) => key,
+                AppEvent::Resize => continue,
+                AppEvent::Tick => {
+                    if app.status.as_ref().is_some_and(|status| status.expired()) {
+                        app.status = None;
+                    }
+                    continue;
+                }
+                // Pick up todos added or changed from another terminal
+                AppEvent::DbChanged => {
+                    if let Err(e) = app.reload_todos() {
+                        app.notify_error(format!("Error reloading todos: {}", e));
+                    }
+                    continue;
+                }
+                AppEvent::Ai(request, event) => {
+                    if let Some(panel) = app.ai_panel.as_mut() {
+                        panel.handle(request, event);
+                    }
+                    continue;
+                }
+            };
+            if app.form.is_some() {
+                app.handle_form_key(key);
+                continue;
+            }
+            if app.filter.open {
+                app.handle_filter_key(key);
+                continue;
+            }
+            if app.confirm.is_some() {
+                app.handle_confirm_key(key);
+                continue;
+            }
+            if app.prompt.is_some() {
+                app.handle_prompt_key(key);
+                continue;
+            }
+            if app.ai_panel.is_some() {
+                app.handle_ai_key(key);
+                continue;
+            }
+            // Any key closes the help
+            if app.show_help {
+                app.show_help = false;
+                continue;
+            }
+            if app.show_delete_confirmation {
+                match app.keymap.action(Scope::Confirm, key) {
+                    Some(Action::Yes) => {
+                        if let Err(e) = app.delete_current_todo() {
+                            app.notify_error(format!("Error deleting todo: {}", e));
                         }
+                        app.show_delete_confirmation = false;
                     }
+                    Some(Action::No) => app.show_delete_confirmation = false,
+                    _ => {}
+                }
+                continue;
+            }
 
-                    KeyCode::Char('d') => {
-                        if let Some(selected) = app.state.selected() {
-                            if selected < app.todos.len() {
-                                let id = app.todos[selected].id;
-                                let status = "Done".to_string();
-                                if let Err(e) = app.change_todo_status(id as i32, status) {
-                                    eprintln!("Error updating todo status: {}", e);
-                                }
-                            }
-                        }
+            let Some(action) = app.keymap.action(Scope::Main, key) else {
+                continue;
+            };
+            match action {
+                Action::Quit => break,
+                Action::Help => app.show_help = true,
+
+                // Switch between the table, the board and the calendar
+                Action::NextView if !app.show_modal => app.toggle_view(),
+
+                // The arrows mean something else in every view
+                Action::Up | Action::Down => {
+                    let step = if action == Action::Up { -1 } else { 1 };
+                    match app.view {
+                        View::Table if step < 0 => app.previous(),
+                        View::Table => app.next(),
+                        View::Board => app.board_move_card(step),
+                        View::Calendar => app.calendar_move_day(step as i64 * 7),
+                    }
+                }
+                Action::Left | Action::Right => {
+                    let step = if action == Action::Left { -1 } else { 1 };
+                    match app.view {
+                        View::Table => {}
+                        View::Board => app.board_move_column(step),
+                        View::Calendar => app.calendar_move_day(step as i64),
                     }
+                }
 
-                    KeyCode::Char('o') => {
-                        if let Some(selected) = app.state.selected() {
-                            if selected < app.todos.len() {
-                                let id = app.todos[selected].id;
-                                let status = "Ongoing".to_string();
-                                if let Err(e) = app.change_todo_status(id as i32, status) {
-                                    eprintln!("Error updating todo status: {}", e);
-                                }
-                            }
-                        }
+                // Board
+                Action::MoveLeft | Action::MoveRight if app.view == View::Board => {
+                    let step = if action == Action::MoveLeft { -1 } else { 1 };
+                    if let Err(e) = app.board_move_todo(step) {
+                        app.notify_error(format!("Error updating todo status: {}", e));
                     }
+                }
 
-                    // Delete todo
-                    KeyCode::Delete => {
-                        if !app.todos.is_empty() {
-                            app.show_delete_confirmation = true;
-                        }
+                // Calendar
+                Action::PreviousMonth if app.view == View::Calendar => app.calendar_move_month(-1),
+                Action::NextMonth if app.view == View::Calendar => app.calendar_move_month(1),
+                Action::PreviousInDay if app.view == View::Calendar => app.calendar_move_todo(-1),
+                Action::NextInDay if app.view == View::Calendar => app.calendar_move_todo(1),
+                Action::DueLater
+                | Action::DueEarlier
+                | Action::DueWeekLater
+                | Action::DueWeekEarlier
+                    if app.view == View::Calendar =>
+                {
+                    let days = match action {
+                        Action::DueLater => 1,
+                        Action::DueEarlier => -1,
+                        Action::DueWeekLater => 7,
+                        _ => -7,
+                    };
+                    if let Err(e) = app.move_due(days) {
+                        app.notify_error(format!("Error moving the due date: {}", e));
                     }
+                }
 
-                    // Handle delete confirmation
-                    KeyCode::Char('y') if app.show_delete_confirmation => {
-                        if let Err(e) = app.delete_current_todo() {
-                            eprintln!("Error deleting todo: {}", e);
-                        }
-                        app.show_delete_confirmation = false;
+                // Group the table
+                Action::Group if app.view == View::Table && !app.show_modal => app.cycle_group_by(),
+                Action::NextGroup if app.view == View::Table => app.move_group(1),
+                Action::PreviousGroup if app.view == View::Table => app.move_group(-1),
+
+                // Open the AI panel
+                Action::Ai if !app.show_modal => {
+                    app.ai_panel = Some(AiPanel::new(events.sender(), ai_client.clone()));
+                }
+
+                // Filter the todos, stepping through the matches
+                Action::Filter if !app.show_modal => app.filter.open = true,
+                Action::NextMatch if app.filter.is_active() => app.next(),
+                Action::PreviousMatch if app.filter.is_active() => app.previous(),
+
+                // Add or edit a todo
+                Action::Add if !app.show_modal => app.form = Some(TodoForm::add()),
+                Action::Edit => {
+                    if let Some(todo) = app.selected().cloned() {
+                        app.form = Some(TodoForm::edit(&todo));
+                        app.close_modal();
                     }
+                }
 
-                    KeyCode::Char('n') if app.show_delete_confirmation => {
-                        app.show_delete_confirmation = false;
+                // Mark todos, then act on all of them at once
+                Action::Mark => app.toggle_mark(),
+                Action::MarkRange => app.mark_range(),
+                Action::MarkAll => app.mark_all(),
+
+                // CHANGE TODO STATUS, of every marked todo if there are any
+                Action::Pending | Action::Ongoing | Action::Done => {
+                    let status = match action {
+                        Action::Pending => "Pending",
+                        Action::Ongoing => "Ongoing",
+                        _ => "Done",
                     }
-                    KeyCode::Char('q') => break,
-                    KeyCode::Down | KeyCode::Char('j') => app.next(),
-                    KeyCode::Up | KeyCode::Char('k') => app.previous(),
-                    KeyCode::Enter => {
-                        if app.show_modal {
-                            app.close_modal();
-                        } else {
-                            app.select_current();
-                        }
+                    .to_string();
+                    if !app.marked.is_empty() {
+                        app.request_bulk(BulkAction::Status(status));
+                    } else if let Some(id) = app.selected().map(|todo| todo.id)
+                        && let Err(e) = app.change_todo_status(id as i32, status)
+                    {
+                        app.notify_error(format!("Error updating todo status: {}", e));
                     }
-                    KeyCode::Esc => {
-                        if app.show_modal {
-                            app.close_modal();
-                        }
+                }
+
+                // Delete todo
+                Action::Delete if !app.marked.is_empty() => app.request_bulk(BulkAction::Delete),
+                Action::Delete => {
+                    if !app.todos.is_empty() {
+                        app.show_delete_confirmation = true;
                     }
-                    _ => {}
                 }
+
+                Action::Owner | Action::Topic | Action::Due if !app.show_modal => {
+                    let field = match action {
+                        Action::Owner => PromptField::Owner,
+                        Action::Topic => PromptField::Topic,
+                        _ => PromptField::Due,
+                    };
+                    if !app.bulk_targets().is_empty() {
+                        app.prompt = Some(BulkPrompt::new(field));
+                    }
+                }
+
+                Action::Open => {
+                    if app.show_modal {
+                        app.close_modal();
+                    } else if app.view == View::Table && app.selected_group().is_some() {
+                        app.toggle_group();
+                    } else {
+                        app.select_current();
+                    }
+                }
+                Action::Clear => {
+                    if app.show_modal {
+                        app.close_modal();
+                    } else if !app.marked.is_empty() || app.range_anchor.is_some() {
+                        app.clear_marks();
+                    } else if app.filter.is_active() {
+                        app.filter.clear();
+                        app.apply_filter();
+                    }
+                }
+                _ => {}
             }
         }
 
@@ -256,22 +992,85 @@ async fn main() -> Result<(), io::Error> {
         )?;
         terminal.show_cursor()?;
     }
+    // Subcommands
+    else if let Some(command) = cli.command {
+        match command {
+            Commands::Ai {
+                show_request,
+                command,
+            } => {
+                let (result, action) = match command {
+                    AiCommand::Add { text, yes } => (
+                        ai::add::add_from_prompt(text.join(" "), yes, show_request).await,
+                        "adding todo",
+                    ),
+                    AiCommand::Breakdown { id } => (
+                        ai::breakdown::breakdown(id, show_request).await,
+                        "breaking down todo",
+                    ),
+                    AiCommand::Agent {
+                        prompt,
+                        yes,
+                        dry_run,
+                    } => (
+                        ai::agent::run_agent(prompt.join(" "), yes, dry_run, show_request).await,
+                        "running the agent",
+                    ),
+                    AiCommand::Chat { resume, list } => {
+                        let result = if list {
+                            ai::chat::list_conversations()
+                        } else {
+                            ai::chat::chat(resume, show_request).await
+                        };
+                        (result, "in chat")
+                    }
+                    AiCommand::Standup { since, plain } => (
+                        ai::standup::standup(&since, plain, show_request).await,
+                        "writing the standup",
+                    ),
+                    AiCommand::Triage { yes } => (
+                        ai::triage::triage(yes, show_request).await,
+                        "triaging todos",
+                    ),
+                    AiCommand::Usage { days } => (ai::usage::usage(days), "reading AI usage"),
+                };
+                match result {
+                    Ok(()) => {}
+                    // The request was printed instead of sent
+                    Err(e) if e.is::<ai::privacy::RequestShown>() => println!("\n{}", e),
+                    Err(e) => eprintln!("Error {}: {}", action, e),
+                }
+            }
+            Commands::Similar { id, limit } => {
+                if let Err(e) = ai::similar::similar(id, limit).await {
+                    eprintln!("Error finding similar todos: {}", e);
+                }
+            }
+        }
+    }
     // Export TODOs into Excel File
     else if cli.export {
         let _workbook = xls::export_todos();
     }
-    // PROMPT GEMINI
+    // PROMPT THE AI
     else if let Some(prompt) = cli.prompt {
-        match ai::ask_gemini(prompt).await {
-            Ok(response) => {
-                println!("");
-                println!("🤖 {}", response);
-                println!("")
-            }
-            Err(e) => eprintln!(
-                "Error: {}. Please set an API key first using the -k flag.",
-                e
-            ),
+        println!();
+        if !cli.show_request {
+            print!("🤖 ");
+        }
+        let mut print_token = |token: &str| {
+            print!("{}", token);
+            let _ = io::stdout().flush();
+        };
+
+        // Ctrl-C stops the answer mid-stream
+        tokio::select! {
+            result = ai::ask(prompt, cli.show_request, &mut print_token) => match result {
+                Ok(_) => println!("\n"),
+                Err(e) if e.is::<ai::privacy::RequestShown>() => println!("\n{}", e),
+                Err(e) => eprintln!("\nError: {}", e),
+            },
+            _ = tokio::signal::ctrl_c() => println!("\n\n⏹  Cancelled"),
         }
     }
     // Print version
@@ -280,38 +1079,57 @@ async fn main() -> Result<(), io::Error> {
     }
     // Pass the API key
     else if let Some(key) = cli.apikey {
-        let db = database::DBtodo::new().unwrap();
-        db.set_api_credentials(Some(key)).unwrap_or_else(|e| {
-            eprintln!("Error setting API credentials: {}", e);
-        })
+        eprintln!("⚠️  Keys passed with -k end up in your shell history, prefer --apikey-stdin");
+        save_api_key(&key);
+    } else if cli.apikey_stdin {
+        match credentials::read_key_from_stdin() {
+            Ok(key) => save_api_key(&key),
+            Err(e) => eprintln!("Error reading API key: {}", e),
+        }
     }
     // Add new todo
     else if let Some(words) = cli.add {
         let text = words.join(" ");
         let desc = cli.desc.map(|desc| desc.join(" "));
-        match arguments::add_todo::add_todo(text, cli.topic, cli.priority, cli.owner, cli.due, desc)
+        match arguments::add_todo::add_todo(
+            text,
+            cli.topic,
+            cli.priority,
+            cli.owner,
+            cli.due,
+            desc,
+            cli.allow_duplicate,
+        )
+        .await
         {
-            Ok(_) => println!("✅ Todo added successfully!"),
+            Ok(Added::New) => println!("✅ Todo added successfully!"),
+            Ok(Added::Merged(id)) => println!("✅ Merged into todo #{}", id),
+            Ok(Added::Cancelled) => println!("Nothing added."),
             Err(e) => eprintln!("Error adding todo: {}", e),
         }
     }
     // Delete todo
     else if let Some(id) = cli.delete {
         match arguments::delete_todo::remove_todo(id) {
-            Ok(_) => println!("✅ Todo deleted successfully!"),
+            Ok(true) => println!("✅ Todo deleted successfully!"),
+            Ok(false) => println!("❌ No todo found with id: {}", id),
             Err(e) => eprintln!("Error deleting todo: {}", e),
         }
     }
     // Update todo status
     else if let (Some(id), Some(status)) = (cli.update_id, cli.status) {
-        if let Err(e) = arguments::update_todo::update_todo(id, status) {
-            eprintln!("Error updating todo: {}", e);
+        match arguments::update_todo::update_todo(id, status) {
+            Ok(true) => {}
+            Ok(false) => println!("❌ No todo found with id: {}", id),
+            Err(e) => eprintln!("Error updating todo: {}", e),
         }
     }
     // UPDATE USING SHORT FORMAT
     else if let Some(id) = cli.done {
-        if let Err(e) = arguments::update_todo::update_todo(id, "Done".to_string()) {
-            eprintln!("Error updating todo: {}", e);
+        match arguments::update_todo::update_todo(id, "Done".to_string()) {
+            Ok(true) => {}
+            Ok(false) => println!("❌ No todo found with id: {}", id),
+            Err(e) => eprintln!("Error updating todo: {}", e),
         }
     }
     // Clear all todos
@@ -342,3 +1160,10 @@ async fn main() -> Result<(), io::Error> {
 
     Ok(())
 }
+
+fn save_api_key(key: &str) {
+    match credentials::store_api_key(key) {
+        Ok(path) => println!("✅ API key saved to {}", path.display()),
+        Err(e) => eprintln!("Error setting API credentials: {}", e),
+    }
+}
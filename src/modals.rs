@@ -11,15 +11,19 @@ use ratatui::{
     widgets::{Block, Borders, Paragraph, Row, Table, Wrap},
 };
 
+use crate::ai::panel::{AiPanel, Focus, suggested_todo};
 use crate::arguments::models::Todo;
+use crate::bulk::{BulkPrompt, Confirm};
+use crate::colors::Theme;
+use crate::form::{Field, TodoForm};
+use crate::keymap::{Action, Key, Keymap, SECTIONS};
 
-pub fn draw_todo_modal(f: &mut Frame, area: Rect, todo: &Todo) {
-    // Elegant purple color palette
-    let background = Color::Rgb(25, 15, 30); // Deep purple
-    let accent = Color::Rgb(150, 80, 220); // Vibrant purple
-    let border = Color::Rgb(180, 140, 220); // Soft lavender
-    let text_primary = Color::Rgb(230, 220, 240); // Light lavender
-    let text_secondary = Color::Rgb(200, 180, 220); // Muted lavender
+pub fn draw_todo_modal(f: &mut Frame, area: Rect, todo: &Todo, theme: &Theme) {
+    let background = theme.background;
+    let accent = theme.accent;
+    let border = theme.border;
+    let text_primary = theme.text_primary;
+    let text_secondary = theme.text_secondary;
 
     // Main modal block with elegant styling
     let block = Block::default()
@@ -45,11 +49,10 @@ pub fn draw_todo_modal(f: &mut Frame, area: Rect, todo: &Todo) {
         Line::from(""),
         Line::from(vec![
             "PRIORITY: ".fg(text_secondary),
-            match todo.priority.to_lowercase().as_str() {
-                "high" => todo.priority.as_str().bold().fg(Color::Rgb(220, 80, 150)), // Pinkish purple
-                "medium" => todo.priority.as_str().bold().fg(Color::Rgb(180, 120, 220)), // Medium purple
-                _ => todo.priority.as_str().bold().fg(Color::Rgb(120, 80, 200)), // Deep purple
-            },
+            todo.priority
+                .as_str()
+                .bold()
+                .fg(theme.priority(&todo.priority)),
         ]),
         Line::from(""),
         Line::from(vec![
@@ -59,13 +62,7 @@ pub fn draw_todo_modal(f: &mut Frame, area: Rect, todo: &Todo) {
         Line::from(""),
         Line::from(vec![
             "STATUS: ".fg(text_secondary),
-            match todo.status.as_str() {
-                "Done" | "Completed" => todo.status.as_str().bold().fg(Color::Rgb(120, 220, 150)), // Soft green
-                "In Progress" => todo.status.as_str().bold().fg(Color::Rgb(220, 180, 100)), // Amber
-                "Planned" => todo.status.as_str().bold().fg(accent),
-                "Backlog" => todo.status.as_str().bold().fg(Color::Rgb(220, 100, 120)), // Soft red
-                _ => todo.status.as_str().bold().fg(accent),
-            },
+            todo.status.as_str().bold().fg(theme.status(&todo.status)),
         ]),
         Line::from(""),
         Line::from(vec![
@@ -83,6 +80,15 @@ pub fn draw_todo_modal(f: &mut Frame, area: Rect, todo: &Todo) {
             todo.text.as_str().bold().fg(text_primary),
         ]),
         Line::from(""),
+        Line::from(vec![
+            "SUBTASK OF: ".fg(text_secondary),
+            todo.parent_id
+                .map(|id| id.to_string())
+                .unwrap_or_else(|| "-".to_string())
+                .bold()
+                .fg(text_primary),
+        ]),
+        Line::from(""),
         Line::from("DESCRIPTION:".fg(text_secondary)),
         Line::from(""),
         Line::from(todo.desc.as_str().fg(text_primary)),
@@ -117,12 +123,10 @@ pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
 }
 
 // DELETE CONFIRMATION MODAL
-pub fn draw_delete_confirmation(f: &mut Frame, area: Rect) {
-    // Purple-themed delete confirmation
-    let background = Color::Rgb(30, 15, 35); // Slightly darker purple
-    let border = Color::Rgb(200, 100, 220); // Bright purple border for warning
-    let text_primary = Color::Rgb(230, 220, 240); // Light lavender
-    let text_secondary = Color::Rgb(200, 180, 220); // Muted lavender
+pub fn draw_delete_confirmation(f: &mut Frame, area: Rect, keymap: &Keymap, theme: &Theme) {
+    let background = theme.background;
+    let border = theme.warning;
+    let text_primary = theme.text_primary;
 
     let block = Block::default()
         .title(" Confirm Delete ")
@@ -133,28 +137,70 @@ pub fn draw_delete_confirmation(f: &mut Frame, area: Rect) {
     let area = centered_rect(40, 20, area);
     f.render_widget(block, area);
 
-    let text = vec![
+    let mut text = vec![
         Line::from("Are you sure you want to delete this item?".fg(text_primary)),
         Line::from(""),
+    ];
+    text.extend(answer_lines("Yes, delete", keymap, theme));
+
+    let paragraph = Paragraph::new(text)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(Block::default().style(Style::default().bg(background)));
+
+    f.render_widget(paragraph, area);
+}
+
+// The yes and no keys of a confirmation, as bound in the keymap
+fn answer_lines(yes: &str, keymap: &Keymap, theme: &Theme) -> [Line<'static>; 2] {
+    let answer = |action: Action, text: String, color: Color| {
         Line::from(vec![
             Span::styled(
-                "Y",
-                Style::default()
-                    .fg(Color::Rgb(120, 220, 150)) // Soft green
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::from(": Yes, delete".fg(text_secondary)),
-        ]),
-        Line::from(vec![
-            Span::styled(
-                "N",
-                Style::default()
-                    .fg(Color::Rgb(220, 100, 120)) // Soft red
-                    .add_modifier(Modifier::BOLD),
+                keymap.label(action),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
             ),
-            Span::from(": Cancel".fg(text_secondary)),
-        ]),
+            format!(": {}", text).fg(theme.text_secondary),
+        ])
+    };
+    [
+        answer(Action::Yes, yes.to_string(), theme.success),
+        answer(Action::No, "Cancel".to_string(), theme.error),
+    ]
+}
+
+// BULK ACTION CONFIRMATION MODAL
+pub fn draw_bulk_confirmation(
+    f: &mut Frame,
+    area: Rect,
+    confirm: &Confirm,
+    keymap: &Keymap,
+    theme: &Theme,
+) {
+    let background = theme.background;
+    let border = theme.warning;
+    let text_primary = theme.text_primary;
+
+    let block = Block::default()
+        .title(" Confirm ")
+        .borders(Borders::ALL)
+        .style(Style::default().bg(background))
+        .border_style(Style::default().fg(border).add_modifier(Modifier::BOLD));
+
+    let area = centered_rect(40, 20, area);
+    f.render_widget(block, area);
+
+    let mut text = vec![
+        Line::from(""),
+        Line::from(
+            confirm
+                .action
+                .question(confirm.ids.len())
+                .bold()
+                .fg(text_primary),
+        ),
+        Line::from(""),
     ];
+    text.extend(answer_lines("Yes, apply to all", keymap, theme));
 
     let paragraph = Paragraph::new(text)
         .alignment(Alignment::Center)
@@ -163,3 +209,434 @@ pub fn draw_delete_confirmation(f: &mut Frame, area: Rect) {
 
     f.render_widget(paragraph, area);
 }
+
+// NEW OWNER, TOPIC OR DUE DATE FOR THE MARKED TODOS
+pub fn draw_bulk_prompt(
+    f: &mut Frame,
+    area: Rect,
+    prompt: &BulkPrompt,
+    count: usize,
+    theme: &Theme,
+) {
+    let background = theme.background;
+    let accent = theme.accent;
+    let text_primary = theme.text_primary;
+    let text_secondary = theme.text_secondary;
+
+    let area = centered_rect(50, 20, area);
+    let block = Block::default()
+        .title(prompt.field.title())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(accent).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(background));
+    let inner_area = block.inner(area);
+    f.render_widget(block, area);
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(1), // Count
+            Constraint::Length(3), // Input
+            Constraint::Min(1),    // Error
+            Constraint::Length(1), // Keys
+        ])
+        .split(inner_area);
+
+    f.render_widget(
+        Paragraph::new(
+            format!("{} todo{}", count, if count == 1 { "" } else { "s" }).fg(text_secondary),
+        ),
+        layout[0],
+    );
+    f.render_widget(
+        Paragraph::new(prompt.value.as_str().fg(text_primary)).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(accent)),
+        ),
+        layout[1],
+    );
+    f.set_cursor_position((
+        layout[1].x + 1 + prompt.value.chars().count() as u16,
+        layout[1].y + 1,
+    ));
+    if let Some(error) = &prompt.error {
+        f.render_widget(
+            Paragraph::new(error.as_str().fg(theme.error)).wrap(Wrap { trim: true }),
+            layout[2],
+        );
+    }
+    f.render_widget(
+        Paragraph::new("Enter: Apply  Esc: Cancel".fg(text_secondary)),
+        layout[3],
+    );
+}
+
+// ADD/EDIT TODO FORM
+pub fn draw_todo_form(f: &mut Frame, area: Rect, form: &TodoForm, theme: &Theme) {
+    let background = theme.background;
+    let accent = theme.accent;
+    let border = theme.border;
+    let text_primary = theme.text_primary;
+    let text_secondary = theme.text_secondary;
+
+    let block = Block::default()
+        .title(form.title())
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(background).fg(text_primary));
+
+    let area = centered_rect(60, 80, area);
+    f.render_widget(block, area);
+
+    let inner_area = area.inner(Margin {
+        vertical: 1,
+        horizontal: 2,
+    });
+
+    let mut constraints = vec![Constraint::Length(3); Field::ALL.len()];
+    constraints.push(Constraint::Min(1)); // Error
+    constraints.push(Constraint::Length(1)); // Keys
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(constraints)
+        .split(inner_area);
+
+    for (field, field_area) in Field::ALL.iter().zip(layout.iter()) {
+        let focused = *field == form.focus;
+        let value = form.value(*field);
+        let content = if *field == Field::Priority {
+            Line::from(vec![
+                "◀ ".fg(text_secondary),
+                value.bold().fg(theme.priority(value)),
+                " ▶".fg(text_secondary),
+            ])
+        } else if value.is_empty() {
+            Line::from(field.placeholder().italic().fg(theme.muted))
+        } else {
+            Line::from(value.fg(text_primary))
+        };
+
+        f.render_widget(
+            Paragraph::new(content).block(
+                Block::default()
+                    .title(format!(" {} ", field.label()))
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(if focused {
+                        accent
+                    } else {
+                        text_secondary
+                    })),
+            ),
+            *field_area,
+        );
+        if focused && *field != Field::Priority {
+            f.set_cursor_position((
+                field_area.x + 1 + value.chars().count() as u16,
+                field_area.y + 1,
+            ));
+        }
+    }
+
+    let errors = Field::ALL.len();
+    if let Some(error) = &form.error {
+        f.render_widget(
+            Paragraph::new(error.as_str().fg(theme.error)).wrap(Wrap { trim: true }),
+            layout[errors],
+        );
+    }
+
+    let keys = if form.focus == Field::Priority {
+        "←/→: Priority  Tab/↓: Next  Shift-Tab/↑: Previous  Enter: Save  Esc: Cancel"
+    } else {
+        "Tab/↓: Next  Shift-Tab/↑: Previous  Enter: Save  Esc: Cancel"
+    };
+    f.render_widget(
+        Paragraph::new(keys.fg(text_secondary)).wrap(Wrap { trim: true }),
+        layout[errors + 1],
+    );
+}
+
+// KEYS HELP, generated from the active keymap
+pub fn draw_help(f: &mut Frame, area: Rect, keymap: &Keymap, theme: &Theme) {
+    let block = Block::default()
+        .title(" Keys (any key closes) ")
+        .borders(Borders::ALL)
+        .border_style(
+            Style::default()
+                .fg(theme.border)
+                .add_modifier(Modifier::BOLD),
+        )
+        .style(Style::default().bg(theme.background));
+
+    let area = centered_rect(90, 90, area);
+    f.render_widget(block, area);
+
+    let inner_area = area.inner(Margin {
+        vertical: 1,
+        horizontal: 2,
+    });
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(1, 3); 3])
+        .split(inner_area);
+
+    let lines = |sections: &[(&str, &[Action])]| {
+        let label = |action: &Action| {
+            let keys = keymap.label(*action);
+            if keys.is_empty() {
+                "-".to_string()
+            } else {
+                keys
+            }
+        };
+        // Keys line up within a column, as narrow as its longest label
+        let width = sections
+            .iter()
+            .flat_map(|(_, actions)| actions.iter())
+            .map(|action| label(action).chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut lines = Vec::new();
+        for (title, actions) in sections {
+            lines.push(Line::from(title.to_string().bold().fg(theme.accent)));
+            for action in actions.iter() {
+                lines.push(Line::from(vec![
+                    format!("  {:<width$}  ", label(action)).fg(theme.text_primary),
+                    action.description().fg(theme.text_secondary),
+                ]));
+            }
+            lines.push(Line::from(""));
+        }
+        lines
+    };
+
+    // Moving around and selecting, changing todos, then the AI panel and the confirmations
+    let (left, rest) = SECTIONS.split_at(4);
+    let (middle, right) = rest.split_at(3);
+    f.render_widget(Paragraph::new(lines(left)), columns[0]);
+    f.render_widget(Paragraph::new(lines(middle)), columns[1]);
+    f.render_widget(Paragraph::new(lines(right)), columns[2]);
+}
+
+// AI SIDE PANEL
+pub fn draw_ai_panel(f: &mut Frame, area: Rect, panel: &AiPanel, keymap: &Keymap, theme: &Theme) {
+    let background = theme.background;
+    let accent = theme.accent;
+    let border = theme.border;
+    let text_primary = theme.text_primary;
+    let text_secondary = theme.text_secondary;
+
+    let block = Block::default()
+        .title(" AI ")
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border).add_modifier(Modifier::BOLD))
+        .style(Style::default().bg(background).fg(text_primary));
+    f.render_widget(block, area);
+
+    let inner_area = area.inner(Margin {
+        vertical: 1,
+        horizontal: 1,
+    });
+
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),                                  // Title
+            Constraint::Min(1),                                     // Answer
+            Constraint::Length(panel.suggestions.len() as u16 + 1), // Suggestions
+            Constraint::Length(1),                                  // Status
+            Constraint::Length(3),                                  // Prompt
+            Constraint::Length(1),                                  // Keys
+        ])
+        .split(inner_area);
+
+    let title = if panel.title.is_empty() {
+        "Ask about your todos or pick a quick action".to_string()
+    } else {
+        panel.title.clone()
+    };
+    f.render_widget(
+        Paragraph::new(title.bold().fg(accent)).wrap(Wrap { trim: true }),
+        layout[0],
+    );
+
+    // Only the first key of each action, like the footer, the help lists them all
+    let label = |actions: &[Action]| {
+        actions
+            .iter()
+            .filter_map(|action| keymap.keys(*action).first())
+            .map(Key::label)
+            .collect::<Vec<String>>()
+            .join("/")
+    };
+
+    let answer = if panel.response.is_empty() && !panel.is_busy() {
+        [
+            (Action::AiSummarise, "summarise the selected todo"),
+            (Action::AiSuggestNext, "suggest the next task"),
+            (Action::AiRewrite, "rewrite the selected todo's description"),
+            (Action::AiPrompt, "write your own prompt"),
+        ]
+        .into_iter()
+        .map(|(action, text)| {
+            Line::from(format!("{}: {}", label(&[action]), text).fg(text_secondary))
+        })
+        .collect()
+    } else {
+        markdown_lines(&panel.response, theme)
+    };
+    f.render_widget(
+        Paragraph::new(answer)
+            .wrap(Wrap { trim: false })
+            .scroll((panel.scroll, 0)),
+        layout[1],
+    );
+
+    let mut suggestions = vec![Line::from("")];
+    for (i, suggestion) in panel.suggestions.iter().take(9).enumerate() {
+        suggestions.push(Line::from(vec![
+            Span::styled(
+                format!("{}", i + 1),
+                Style::default()
+                    .fg(theme.success)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            format!(": {}", suggestion.label()).fg(text_secondary),
+        ]));
+    }
+    f.render_widget(Paragraph::new(suggestions), layout[2]);
+
+    let status = if let Some(error) = &panel.error {
+        format!("Error: {}", error).fg(theme.error)
+    } else if panel.is_busy() {
+        "Thinking...".italic().fg(text_secondary)
+    } else if let Some(notice) = &panel.notice {
+        notice.clone().fg(theme.success)
+    } else {
+        "".into()
+    };
+    f.render_widget(Paragraph::new(status), layout[3]);
+
+    let input_border = match panel.focus {
+        Focus::Input => accent,
+        Focus::Actions => text_secondary,
+    };
+    f.render_widget(
+        Paragraph::new(panel.input.as_str().fg(text_primary)).block(
+            Block::default()
+                .title(" Prompt ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(input_border)),
+        ),
+        layout[4],
+    );
+    if panel.focus == Focus::Input {
+        f.set_cursor_position((
+            layout[4].x + 1 + panel.input.chars().count() as u16,
+            layout[4].y + 1,
+        ));
+    }
+
+    let scroll = label(&[Action::AiScrollUp, Action::AiScrollDown]);
+    let keys = match panel.focus {
+        Focus::Input => "Enter: Send  Esc: Back".to_string(),
+        Focus::Actions if panel.is_busy() => {
+            format!("{}: Scroll  {}: Stop", scroll, label(&[Action::AiClose]))
+        }
+        Focus::Actions => format!(
+            "{}: Actions  1-9: Apply  {}: Prompt  {}: Scroll  {}: Close",
+            label(&[
+                Action::AiSummarise,
+                Action::AiSuggestNext,
+                Action::AiRewrite
+            ]),
+            label(&[Action::AiPrompt]),
+            scroll,
+            label(&[Action::AiClose])
+        ),
+    };
+    f.render_widget(Paragraph::new(keys.fg(text_secondary)), layout[5]);
+}
+
+// Render the small subset of markdown models tend to answer with
+fn markdown_lines(text: &str, theme: &Theme) -> Vec<Line<'static>> {
+    let accent = theme.accent;
+    let text_primary = theme.text_primary;
+    let text_secondary = theme.text_secondary;
+    let mut lines = Vec::new();
+    let mut in_code = false;
+
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            lines.push(Line::from(format!("  {}", line).fg(text_secondary)));
+            continue;
+        }
+
+        if let Some(todo) = suggested_todo(line) {
+            lines.push(Line::from(format!("＋ {}", todo).bold().fg(theme.success)));
+        } else if trimmed.starts_with('#') {
+            let heading = trimmed.trim_start_matches('#').trim().to_string();
+            lines.push(Line::from(heading.bold().fg(accent)));
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            let indent = " ".repeat(line.len() - trimmed.len());
+            let mut spans = vec![format!("{}• ", indent).fg(accent)];
+            spans.extend(inline_spans(item, text_primary, accent));
+            lines.push(Line::from(spans));
+        } else {
+            lines.push(Line::from(inline_spans(line, text_primary, accent)));
+        }
+    }
+    lines
+}
+
+// **bold** and `code` inside a line
+fn inline_spans(text: &str, text_primary: Color, accent: Color) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut bold = false;
+    let mut code = false;
+    let mut current = String::new();
+    let mut chars = text.chars().peekable();
+
+    let style = |bold: bool, code: bool| match (bold, code) {
+        (_, true) => Style::default().fg(accent),
+        (true, false) => Style::default()
+            .fg(text_primary)
+            .add_modifier(Modifier::BOLD),
+        (false, false) => Style::default().fg(text_primary),
+    };
+
+    while let Some(c) = chars.next() {
+        let toggle_bold = c == '*' && chars.peek() == Some(&'*') && !code;
+        if toggle_bold || c == '`' {
+            if !current.is_empty() {
+                spans.push(Span::styled(
+                    std::mem::take(&mut current),
+                    style(bold, code),
+                ));
+            }
+            if toggle_bold {
+                chars.next();
+                bold = !bold;
+            } else {
+                code = !code;
+            }
+            continue;
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        spans.push(Span::styled(current, style(bold, code)));
+    }
+    spans
+}
@@ -0,0 +1,40 @@
+use std::time::{Duration, Instant};
+
+// Errors stay up longer so there's time to read them
+const SUCCESS_TIMEOUT: Duration = Duration::from_secs(3);
+const ERROR_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// A message in the TUI's status bar that clears itself after a few seconds
+#[derive(Debug)]
+pub struct StatusMessage {
+    pub text: String,
+    pub is_error: bool,
+    shown: Instant,
+}
+
+impl StatusMessage {
+    pub fn success(text: String) -> Self {
+        Self {
+            text,
+            is_error: false,
+            shown: Instant::now(),
+        }
+    }
+
+    pub fn error(text: String) -> Self {
+        Self {
+            text,
+            is_error: true,
+            shown: Instant::now(),
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        let timeout = if self.is_error {
+            ERROR_TIMEOUT
+        } else {
+            SUCCESS_TIMEOUT
+        };
+        self.shown.elapsed() > timeout
+    }
+}
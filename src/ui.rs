@@ -1,6 +1,14 @@
-use crate::App;
 use crate::arguments::models::Todo;
-use crate::modals::{centered_rect, draw_delete_confirmation, draw_todo_modal};
+use crate::board::draw_board;
+use crate::calendar::draw_calendar;
+use crate::colors::Theme;
+use crate::groups::{GroupBy, TableRow};
+use crate::keymap::{Action, Key, Keymap};
+use crate::modals::{
+    centered_rect, draw_ai_panel, draw_bulk_confirmation, draw_bulk_prompt,
+    draw_delete_confirmation, draw_help, draw_todo_form, draw_todo_modal,
+};
+use crate::{App, View};
 use ratatui::layout::Alignment;
 use ratatui::prelude::Stylize;
 use ratatui::text::Span;
@@ -9,41 +17,65 @@ use ratatui::{
     Frame, Terminal,
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout, Margin, Rect},
-    style::{Color, Modifier, Style},
+    style::{Modifier, Style},
     text::Line,
-    widgets::{Block, Borders, Paragraph, Row, Table, Wrap},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, Wrap},
 };
 
 // MAIN UI
 pub fn draw_ui(f: &mut Frame, app: &mut App) {
     let area = f.area();
 
-    // Elegant purple color palette
-    let background = Color::Rgb(25, 15, 30); // Deep purple
-    let accent = Color::Rgb(150, 80, 220); // Vibrant purple
-    let border = Color::Rgb(180, 140, 220); // Soft lavender
-    let text_primary = Color::Rgb(230, 220, 240); // Light lavender
-    let text_secondary = Color::Rgb(200, 180, 220); // Muted lavender
-    let highlight = Color::Rgb(50, 30, 60); // Darker purple
+    // Colours of the configured theme
+    let theme = &app.theme;
+    let background = theme.background;
+    let accent = theme.accent;
+    let border = theme.border;
+    let text_primary = theme.text_primary;
+    let text_secondary = theme.text_secondary;
+    let highlight = theme.highlight;
 
     // Handle modal and delete confirmation states first
+    if app.show_help {
+        draw_help(f, area, &app.keymap, theme);
+        return;
+    }
+
     if app.show_delete_confirmation {
-        draw_delete_confirmation(f, area);
+        draw_delete_confirmation(f, area, &app.keymap, theme);
+        return;
+    }
+
+    if let Some(confirm) = &app.confirm {
+        draw_bulk_confirmation(f, area, confirm, &app.keymap, theme);
+        return;
+    }
+
+    if let Some(prompt) = &app.prompt {
+        draw_bulk_prompt(f, area, prompt, app.bulk_targets().len(), theme);
+        return;
+    }
+
+    if let Some(form) = &app.form {
+        draw_todo_form(f, area, form, theme);
         return;
     }
 
     if app.show_modal {
-        draw_todo_modal(f, area, app.selected_todo.as_ref().unwrap());
+        draw_todo_modal(f, area, app.selected_todo.as_ref().unwrap(), theme);
         return;
     }
 
     // Main table view layout
+    let show_filter = app.filter.open || app.filter.is_active();
     let layout = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Min(1),    // Main table area
-            Constraint::Length(3), // Stats area
-            Constraint::Length(1), // Shortcuts area
+            Constraint::Min(1),                                  // Main table area
+            Constraint::Length(if show_filter { 3 } else { 0 }), // Filter bar
+            Constraint::Length(3),                               // Stats area
+            Constraint::Length(1),                               // Status bar
+            Constraint::Length(1),                               // Shortcuts area
         ])
         .split(area);
 
@@ -54,33 +86,68 @@ pub fn draw_ui(f: &mut Frame, app: &mut App) {
     .style(Style::default().fg(accent).add_modifier(Modifier::BOLD));
 
     // Table rows with status-based coloring
-    let rows = app.todos.iter().map(|todo| {
+    let todo_row = |todo: &Todo| {
+        let marked = app.marked.contains(&todo.id);
         Row::new(vec![
-            todo.id.to_string().fg(text_primary),
-            match todo.priority.to_lowercase().as_str() {
-                "high" | "High" | "HIGH" => todo.priority.clone().fg(Color::Rgb(220, 80, 150)), // Pinkish purple
-                "medium" | "Medium" | "MEDIUM" => {
-                    todo.priority.clone().fg(Color::Rgb(180, 120, 120))
-                } // Medium Yellow
-                "low" | "Low" | "LOW" => todo.priority.clone().fg(Color::Rgb(120, 80, 200)), // Deep purple
-                _ => todo.priority.clone().fg(Color::Rgb(120, 80, 200)), // Deep purple
+            if marked {
+                format!("● {}", todo.id).fg(accent).bold()
+            } else {
+                todo.id.to_string().fg(text_primary)
             },
+            todo.priority.clone().fg(theme.priority(&todo.priority)),
             todo.topic.clone().fg(text_primary),
-            todo.text.clone().fg(text_secondary),
+            match todo.parent_id {
+                Some(_) => format!("↳ {}", todo.text).fg(text_secondary),
+                None => todo.text.clone().fg(text_secondary),
+            },
             todo.date_added.clone().fg(text_secondary),
             todo.due.clone().fg(text_secondary),
-            match todo.status.as_str() {
-                "Done" | "Completed" => todo.status.clone().fg(Color::Rgb(120, 220, 150)), // Soft green
-                "Ongoing" => todo.status.clone().fg(Color::Rgb(220, 180, 100)),            // Amber
-                "Planned" => todo.status.clone().fg(accent),
-                "Pending" => todo.status.clone().fg(Color::Rgb(220, 100, 120)), // Soft red
-                _ => todo.status.clone().fg(text_primary),
-            },
+            todo.status.clone().fg(theme.status(&todo.status)),
             todo.owner
                 .clone()
                 .fg(text_primary)
                 .style(Style::default().add_modifier(Modifier::ITALIC)),
         ])
+        .style(if marked {
+            Style::default().bg(theme.marked)
+        } else {
+            Style::default()
+        })
+    };
+
+    // Group headers show the group in its own column and the counts under TODO
+    let group_row = |name: &str, count: usize, done: usize, collapsed: bool| {
+        let mut cells = vec![Cell::from(""); 8];
+        cells[0] = Cell::from(if collapsed { "▸" } else { "▾" }.fg(accent));
+        let column = match app.group_by {
+            GroupBy::Priority => 1,
+            GroupBy::Topic => 2,
+            GroupBy::Due => 5,
+            GroupBy::Status => 6,
+            _ => 7,
+        };
+        cells[column] = Cell::from(name.to_string().bold().fg(accent));
+        cells[3] = Cell::from(
+            format!(
+                "{} todo{}, {} done",
+                count,
+                if count == 1 { "" } else { "s" },
+                done
+            )
+            .fg(text_secondary),
+        );
+        Row::new(cells).style(Style::default().add_modifier(Modifier::BOLD))
+    };
+
+    let table_rows = app.table_rows();
+    let rows = table_rows.iter().map(|row| match row {
+        TableRow::Todo(index) => todo_row(&app.todos[*index]),
+        TableRow::Group {
+            name,
+            count,
+            done,
+            collapsed,
+        } => group_row(name, *count, *done, *collapsed),
     });
 
     // Elegant table styling
@@ -100,7 +167,10 @@ pub fn draw_ui(f: &mut Frame, app: &mut App) {
     .header(header)
     .block(
         Block::default()
-            .title(" RustyDO ")
+            .title(match app.group_by {
+                GroupBy::None => " RustyDO ".to_string(),
+                group_by => format!(" RustyDO · grouped by {} ", group_by.label()),
+            })
             .borders(Borders::ALL)
             .border_style(Style::default().fg(border))
             .style(Style::default().bg(background)),
@@ -108,32 +178,114 @@ pub fn draw_ui(f: &mut Frame, app: &mut App) {
     .highlight_style(Style::default().bg(highlight).fg(text_primary))
     .row_highlight_style(
         Style::default()
-            .bg(Color::Rgb(120, 80, 190))
-            .fg(Color::Rgb(255, 255, 255)),
+            .bg(theme.selection)
+            .fg(theme.selection_text),
     )
     .column_spacing(1);
 
-    f.render_stateful_widget(table, layout[0], &mut app.state);
+    // The AI panel takes the right side of the table area
+    let table_area = match &app.ai_panel {
+        Some(panel) => {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(55), Constraint::Percentage(45)])
+                .split(layout[0]);
+            draw_ai_panel(f, columns[1], panel, &app.keymap, theme);
+            columns[0]
+        }
+        None => layout[0],
+    };
+
+    match app.view {
+        View::Table if app.group_by == GroupBy::None => {
+            f.render_stateful_widget(table, table_area, &mut app.state)
+        }
+        // Header rows shift the todos, so the cursor is placed by row
+        View::Table => {
+            let mut state = TableState::default().with_selected(app.current_row(&table_rows));
+            f.render_stateful_widget(table, table_area, &mut state)
+        }
+        View::Board => draw_board(f, table_area, app),
+        View::Calendar => draw_calendar(f, table_area, app),
+    }
+
+    if show_filter {
+        let filter_border = if app.filter.open {
+            accent
+        } else {
+            text_secondary
+        };
+        let title = if app.filter.open {
+            " Filter (status: owner: topic: priority:) "
+        } else {
+            " Filter (/: Edit  n/N: Next/Previous  Esc: Clear) "
+        };
+        f.render_widget(
+            Paragraph::new(app.filter.query.as_str().fg(text_primary)).block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(filter_border))
+                    .style(Style::default().bg(background)),
+            ),
+            layout[1],
+        );
+        if app.filter.open {
+            f.set_cursor_position((
+                layout[1].x + 1 + app.filter.query.chars().count() as u16,
+                layout[1].y + 1,
+            ));
+        }
+    }
 
     // Stats with elegant styling
-    let stats_spans = calculate_stats(&app.todos);
+    let mut stats_spans = calculate_stats(&app.all_todos, theme);
+    if !app.marked.is_empty() {
+        stats_spans.push(Span::raw(" | SELECTED: "));
+        stats_spans.push(Span::styled(
+            app.marked.len().to_string(),
+            Style::default().fg(accent),
+        ));
+    }
+    if let Some(anchor) = app.range_anchor {
+        stats_spans.push(Span::raw(format!(" | RANGE FROM #{} (V to end)", anchor)));
+    }
+    if app.filter.is_active() {
+        stats_spans.push(Span::raw(" | MATCHES: "));
+        stats_spans.push(Span::styled(
+            app.todos.len().to_string(),
+            Style::default().fg(accent),
+        ));
+    }
     let status_line = Paragraph::new(Line::from(stats_spans)).block(
         Block::default()
             .border_style(Style::default().fg(border))
             .style(Style::default().bg(background)),
     );
 
-    f.render_widget(status_line, layout[1]);
+    f.render_widget(status_line, layout[2]);
+
+    // Last success or error message
+    let message = match &app.status {
+        Some(status) if status.is_error => format!(" ✖ {}", status.text).fg(theme.error),
+        Some(status) => format!(" ✔ {}", status.text).fg(theme.success),
+        None => "".into(),
+    };
+    f.render_widget(
+        Paragraph::new(Line::from(message))
+            .block(Block::default().style(Style::default().bg(background))),
+        layout[3],
+    );
 
     // Shortcuts with consistent styling
-    let shortcuts = Paragraph::new(get_shortcuts_text())
+    let shortcuts = Paragraph::new(get_shortcuts_text(app.view, &app.keymap))
         .style(Style::default().fg(text_secondary))
         .block(Block::default().style(Style::default().bg(background)));
 
-    f.render_widget(shortcuts, layout[2]);
+    f.render_widget(shortcuts, layout[4]);
 }
 
-pub fn calculate_stats(todos: &[Todo]) -> Vec<Span<'static>> {
+pub fn calculate_stats(todos: &[Todo], theme: &Theme) -> Vec<Span<'static>> {
     let done = todos.iter().filter(|t| t.status == "Done").count();
     let ongoing = todos.iter().filter(|t| t.status == "Ongoing").count();
     // let planned = todos.iter().filter(|t| t.status == "Planned").count();
@@ -142,19 +294,13 @@ pub fn calculate_stats(todos: &[Todo]) -> Vec<Span<'static>> {
     vec![
         Span::raw(" "),
         Span::raw("TOTAL: "),
-        Span::styled(
-            todos.len().to_string(),
-            Style::default().fg(Color::Rgb(150, 80, 220)), // Purple
-        ),
+        Span::styled(todos.len().to_string(), Style::default().fg(theme.accent)),
         Span::raw(" | Done: "),
-        Span::styled(
-            done.to_string(),
-            Style::default().fg(Color::Rgb(120, 220, 150)), // Green
-        ),
+        Span::styled(done.to_string(), Style::default().fg(theme.status("Done"))),
         Span::raw(" | ONGOING: "),
         Span::styled(
             ongoing.to_string(),
-            Style::default().fg(Color::Rgb(220, 180, 100)), // Amber
+            Style::default().fg(theme.status("Ongoing")),
         ),
         // Span::raw(" | PLANNED: "),
         // Span::styled(
@@ -164,38 +310,76 @@ pub fn calculate_stats(todos: &[Todo]) -> Vec<Span<'static>> {
         Span::raw(" | PENDING: "),
         Span::styled(
             pending.to_string(),
-            Style::default().fg(Color::Rgb(220, 100, 120)), // Red
+            Style::default().fg(theme.status("Pending")),
         ),
     ]
 }
 
-// KEYWBOARD SHORTCUTS
-fn get_shortcuts_text() -> Line<'static> {
-    Line::from(vec![
-        " ".into(),
-        "[ ".into(),
-        "↑/↓: Navigate".into(),
-        " ]".into(),
-        " ".into(),
-        "[ ".into(),
-        "Enter: View".into(),
-        " ]".into(),
-        " ".into(),
-        "[ ".into(),
-        "delete: Delete".into(),
-        " ]".into(),
-        " ".into(),
-        "[ ".into(),
-        "d: Done".into(),
-        " ] ".into(),
-        "[ ".into(),
-        "p: Pending".into(),
-        " ] ".into(),
-        "[ ".into(),
-        "o: Ongoing".into(),
-        " ] ".into(),
-        "[ ".into(),
-        "q: Quit".into(),
-        " ]".into(),
-    ])
+// KEYWBOARD SHORTCUTS, from the active keymap
+fn get_shortcuts_text(view: View, keymap: &Keymap) -> Line<'static> {
+    let view_shortcuts: &[(&[Action], &str)] = match view {
+        View::Table => &[
+            (&[Action::Up, Action::Down], "Navigate"),
+            (&[Action::Group], "Group"),
+            (&[Action::PreviousGroup, Action::NextGroup], "Groups"),
+            (&[Action::NextView], "Board"),
+        ],
+        View::Board => &[
+            (
+                &[Action::Left, Action::Down, Action::Up, Action::Right],
+                "Navigate",
+            ),
+            (&[Action::MoveLeft, Action::MoveRight], "Move card"),
+            (&[Action::NextView], "Calendar"),
+        ],
+        View::Calendar => &[
+            (
+                &[Action::Left, Action::Down, Action::Up, Action::Right],
+                "Day",
+            ),
+            (&[Action::PreviousMonth, Action::NextMonth], "Month"),
+            (&[Action::NextInDay, Action::PreviousInDay], "Todo"),
+            (&[Action::DueLater, Action::DueEarlier], "Due ±1 day"),
+            (
+                &[Action::DueWeekLater, Action::DueWeekEarlier],
+                "Due ±1 week",
+            ),
+            (&[Action::NextView], "Table"),
+        ],
+    };
+    let shortcuts: &[(&[Action], &str)] = &[
+        (&[Action::Open], "View"),
+        (&[Action::Delete], "Delete"),
+        (&[Action::Done], "Done"),
+        (&[Action::Pending], "Pending"),
+        (&[Action::Ongoing], "Ongoing"),
+        (
+            &[Action::Mark, Action::MarkRange, Action::MarkAll],
+            "Select",
+        ),
+        (
+            &[Action::Owner, Action::Topic, Action::Due],
+            "Owner/Topic/Due",
+        ),
+        (&[Action::Add], "Add"),
+        (&[Action::Edit], "Edit"),
+        (&[Action::Filter], "Filter"),
+        (&[Action::Ai], "AI"),
+        (&[Action::Help], "Help"),
+        (&[Action::Quit], "Quit"),
+    ];
+
+    let mut spans = vec![" ".into()];
+    for (actions, label) in view_shortcuts.iter().chain(shortcuts) {
+        // Only the first key of each action, the help lists them all
+        let keys = actions
+            .iter()
+            .filter_map(|action| keymap.keys(*action).first())
+            .map(Key::label)
+            .collect::<Vec<String>>();
+        if !keys.is_empty() {
+            spans.push(format!("[ {}: {} ] ", keys.join("/"), label).into());
+        }
+    }
+    Line::from(spans)
 }